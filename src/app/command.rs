@@ -1,6 +1,88 @@
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// Commands that can be run in command mode, on the notes at the given indices
 pub enum Command {
     Upvote,
     Downvote,
     Group,
+    Ungroup,
+    Suggest,
+    React,
+    ActionItem,
+    Done,
+    Sort,
+    Export,
+    Messages,
+}
+
+impl Command {
+    /// Every command, in the order they're listed in the help box
+    pub const ALL: [Command; 11] = [
+        Command::Group,
+        Command::Ungroup,
+        Command::Suggest,
+        Command::Upvote,
+        Command::Downvote,
+        Command::React,
+        Command::ActionItem,
+        Command::Done,
+        Command::Sort,
+        Command::Export,
+        Command::Messages,
+    ];
+
+    /// Names the command can be invoked by, shortest last
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Command::Upvote => &["vote", "v"],
+            Command::Downvote => &["unvote", "d"],
+            Command::Group => &["group", "g"],
+            Command::Ungroup => &["ungroup", "u"],
+            Command::Suggest => &["suggest", "s"],
+            Command::React => &["react", "r"],
+            Command::ActionItem => &["action", "a"],
+            Command::Done => &["done", "x"],
+            Command::Sort => &["sort", "o"],
+            Command::Export => &["export", "e"],
+            Command::Messages => &["messages", "m"],
+        }
+    }
+
+    /// How to use the command, as shown in the help box
+    pub fn usage(&self) -> String {
+        let (args, description) = match self {
+            Command::Upvote => ("1 2 .. n", "upvote"),
+            Command::Downvote => ("1 2 .. n", "downvote"),
+            Command::Group => ("1 2 .. n", "group [name]"),
+            Command::Ungroup => ("1 2 .. n", "ungroup"),
+            Command::Suggest => ("", "suggest groups"),
+            Command::React => ("1 2 .. n", "👍 react"),
+            Command::ActionItem => ("1 2 .. n", "action item"),
+            Command::Done => ("1 2 .. n", "action done"),
+            Command::Sort => ("key", "sort [asc|desc]"),
+            Command::Export => ("", "export to csv"),
+            Command::Messages => ("", "past messages"),
+        };
+        let alias = self.aliases().last().unwrap();
+
+        format!("{alias:>2}  {args:<8} {description}")
+    }
+
+    /// Parse a command line such as `v 1 2`, into the command, the indices
+    /// it applies to and any other arguments.
+    pub fn parse(line: &str) -> Option<(Command, Vec<usize>, Vec<&str>)> {
+        let mut words = line.split_whitespace();
+        let name = words.next()?;
+
+        let command = Command::ALL
+            .into_iter()
+            .find(|command| command.aliases().contains(&name))?;
+        let (indices, args): (Vec<&str>, Vec<&str>) =
+            words.partition(|word| word.parse::<usize>().is_ok());
+        let indices = indices
+            .iter()
+            .filter_map(|word| word.parse().ok())
+            .collect();
+
+        Some((command, indices, args))
+    }
 }
@@ -0,0 +1,94 @@
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::Utc;
+use firestore_grpc::v1::{value::ValueType, Value};
+
+#[derive(Clone, Debug)]
+/// A short reply to a note
+pub struct Comment {
+    /// Resource name of the comment document
+    pub id: String,
+
+    /// Who wrote the comment
+    pub author: String,
+
+    /// The actual text of the comment
+    pub text: String,
+
+    /// Unix timestamp (seconds) of when the comment was written
+    pub created_at: i64,
+}
+
+impl Comment {
+    /// Create a new comment, written just now
+    pub fn new(author: String, text: String) -> Self {
+        Comment {
+            id: "".to_string(),
+            author,
+            text,
+            created_at: Utc::now().timestamp(),
+        }
+    }
+}
+
+impl Display for Comment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.author, self.text)
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<HashMap<String, Value>> for &Comment {
+    fn into(self) -> HashMap<String, Value> {
+        let mut fields = HashMap::new();
+
+        fields.insert(
+            "author".to_string(),
+            Value {
+                value_type: Some(ValueType::StringValue(self.author.clone())),
+            },
+        );
+
+        fields.insert(
+            "text".to_string(),
+            Value {
+                value_type: Some(ValueType::StringValue(self.text.clone())),
+            },
+        );
+
+        fields.insert(
+            "created_at".to_string(),
+            Value {
+                value_type: Some(ValueType::IntegerValue(self.created_at)),
+            },
+        );
+
+        fields
+    }
+}
+
+impl From<HashMap<String, Value>> for Comment {
+    fn from(values: HashMap<String, Value>) -> Self {
+        let author = match values.get("author").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::StringValue(author)) => author,
+            _ => "".to_string(),
+        };
+
+        let text = match values.get("text").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::StringValue(text)) => text,
+            _ => "".to_string(),
+        };
+
+        let created_at = match values.get("created_at").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::IntegerValue(created_at)) => created_at,
+            _ => 0,
+        };
+
+        Comment {
+            id: "".to_string(),
+            author,
+            text,
+            created_at,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use super::note::Note;
+
+/// How similar two notes must be, between 0 and 1, to be suggested as a group
+const SIMILARITY_THRESHOLD: f64 = 0.3;
+
+/// Words too common to say anything about what a note is about
+const STOP_WORDS: [&str; 48] = [
+    "the", "and", "for", "are", "but", "not", "you", "all", "any", "can", "had", "her", "was",
+    "one", "our", "out", "has", "have", "him", "his", "how", "its", "let", "may", "who", "did",
+    "get", "got", "too", "use", "that", "this", "with", "from", "they", "them", "been", "were",
+    "will", "when", "what", "there", "their", "then", "than", "very", "much", "more",
+];
+
+/// Suggest groups of notes that say more or less the same thing, as indices
+/// into `notes`. Notes are compared by TF-IDF weighted word overlap, every
+/// note that is similar enough to another note in a group joins that group.
+pub fn suggest_groups(notes: &[Note]) -> Vec<Vec<usize>> {
+    let documents: Vec<Vec<String>> = notes.iter().map(|note| tokens(&note.text)).collect();
+    let vectors = tf_idf(&documents);
+
+    let mut parents: Vec<usize> = (0..notes.len()).collect();
+    for a in 0..vectors.len() {
+        for b in a + 1..vectors.len() {
+            if similarity(&vectors[a], &vectors[b]) >= SIMILARITY_THRESHOLD {
+                let (root_a, root_b) = (root(&parents, a), root(&parents, b));
+                parents[root_b.max(root_a)] = root_a.min(root_b);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = vec![vec![]; notes.len()];
+    for index in 0..notes.len() {
+        groups[root(&parents, index)].push(index);
+    }
+
+    groups.into_iter().filter(|group| group.len() > 1).collect()
+}
+
+fn root(parents: &[usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        index = parents[index];
+    }
+
+    index
+}
+
+/// Lowercased, roughly stemmed words of a note, without the stop words
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() > 2 && !STOP_WORDS.contains(&word.as_str()))
+        .map(stem)
+        .collect()
+}
+
+/// Strip the most common suffixes, so "deploys" and "deploying" match
+fn stem(word: String) -> String {
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.chars().count() >= 3 {
+                return stem.to_string();
+            }
+        }
+    }
+
+    word
+}
+
+/// Unit length TF-IDF vectors of the documents
+fn tf_idf(documents: &[Vec<String>]) -> Vec<HashMap<&str, f64>> {
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for document in documents {
+        let mut seen: Vec<&str> = document.iter().map(String::as_str).collect();
+        seen.sort_unstable();
+        seen.dedup();
+
+        for word in seen {
+            *document_frequency.entry(word).or_default() += 1;
+        }
+    }
+
+    let total = documents.len() as f64;
+
+    documents
+        .iter()
+        .map(|document| {
+            let mut vector: HashMap<&str, f64> = HashMap::new();
+            for word in document {
+                *vector.entry(word.as_str()).or_default() += 1.0;
+            }
+
+            for (word, weight) in vector.iter_mut() {
+                let idf = ((1.0 + total) / (1.0 + document_frequency[word] as f64)).ln() + 1.0;
+                *weight = *weight / document.len() as f64 * idf;
+            }
+
+            let length = vector
+                .values()
+                .map(|weight| weight * weight)
+                .sum::<f64>()
+                .sqrt();
+            for weight in vector.values_mut() {
+                *weight /= length;
+            }
+
+            vector
+        })
+        .collect()
+}
+
+/// Cosine similarity of two unit vectors
+fn similarity(a: &HashMap<&str, f64>, b: &HashMap<&str, f64>) -> f64 {
+    a.iter()
+        .filter_map(|(word, weight)| b.get(word).map(|other| weight * other))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::lexicon::Lexicon;
+
+    fn notes(texts: &[&str]) -> Vec<Note> {
+        texts
+            .iter()
+            .map(|text| Note::new("ann".to_string(), text.to_string(), &Lexicon::default()))
+            .collect()
+    }
+
+    #[test]
+    fn nothing_to_group() {
+        assert!(suggest_groups(&[]).is_empty());
+        assert!(suggest_groups(&notes(&["deploys were slow"])).is_empty());
+        assert!(suggest_groups(&notes(&["deploys were slow", "lunch was great"])).is_empty());
+    }
+
+    #[test]
+    fn similar_notes_are_grouped() {
+        let notes = notes(&[
+            "deploys were slow",
+            "lunch was great",
+            "deploying is slow",
+            "standups run long",
+        ]);
+
+        assert_eq!(suggest_groups(&notes), vec![vec![0, 2]]);
+    }
+
+    #[test]
+    fn groups_are_ordered_by_their_notes() {
+        let notes = notes(&[
+            "standups run long",
+            "deploys were slow",
+            "lunch was great",
+            "long standups every day",
+            "slow deploys again",
+            "deploys slow everything",
+        ]);
+
+        assert_eq!(suggest_groups(&notes), vec![vec![0, 3], vec![1, 4, 5]]);
+    }
+
+    #[test]
+    fn words_are_stemmed_without_stop_words() {
+        assert_eq!(
+            tokens("The deploys, deploying and deployed!"),
+            vec!["deploy", "deploy", "deploy"]
+        );
+        assert_eq!(stem("bus".to_string()), "bus");
+    }
+}
@@ -0,0 +1,586 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::mode::Mode;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// Something the user can do by pressing a key
+pub enum Action {
+    /// Quit the retro
+    Quit,
+
+    /// Show or hide the help box
+    ToggleHelp,
+
+    /// Show or hide the participant sidebar
+    ToggleParticipants,
+
+    /// Show or hide the sentiment dashboard
+    ToggleDashboard,
+
+    /// Show or hide the trends of the room's series
+    ToggleTrends,
+
+    /// Switch between wrapping long notes and cutting them off
+    ToggleCompact,
+
+    /// Switch to insert mode to write a new note
+    InsertMode,
+
+    /// Switch to command mode
+    CommandMode,
+
+    /// Go back to normal mode
+    NormalMode,
+
+    /// Publish the note being written
+    PublishNote,
+
+    /// Start a new line in the note being written
+    NewLine,
+
+    /// Run the command being written
+    RunCommand,
+
+    /// Delete the word before the cursor
+    DeleteWord,
+
+    /// Move the cursor one word back
+    WordBack,
+
+    /// Move the cursor one word forward
+    WordForward,
+
+    /// Move the cursor to the previous note
+    CursorUp,
+
+    /// Move the cursor to the next note
+    CursorDown,
+
+    /// Open the note under the cursor
+    OpenNote,
+
+    /// Write a comment on the open note
+    Comment,
+
+    /// Pick a reaction for the note under the cursor
+    React,
+
+    /// Change the text of the open note
+    EditNote,
+
+    /// Delete the open note
+    DeleteNote,
+
+    /// Vote for the open note, or take the vote back
+    ToggleVote,
+}
+
+impl Action {
+    const ALL: [Action; 23] = [
+        Action::Quit,
+        Action::ToggleHelp,
+        Action::ToggleParticipants,
+        Action::ToggleDashboard,
+        Action::ToggleTrends,
+        Action::ToggleCompact,
+        Action::InsertMode,
+        Action::CommandMode,
+        Action::NormalMode,
+        Action::PublishNote,
+        Action::NewLine,
+        Action::RunCommand,
+        Action::DeleteWord,
+        Action::WordBack,
+        Action::WordForward,
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::OpenNote,
+        Action::Comment,
+        Action::React,
+        Action::EditNote,
+        Action::DeleteNote,
+        Action::ToggleVote,
+    ];
+
+    /// Name used to refer to the action in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ToggleHelp => "toggle_help",
+            Action::ToggleParticipants => "toggle_participants",
+            Action::ToggleDashboard => "toggle_dashboard",
+            Action::ToggleTrends => "toggle_trends",
+            Action::ToggleCompact => "toggle_compact",
+            Action::InsertMode => "insert_mode",
+            Action::CommandMode => "command_mode",
+            Action::NormalMode => "normal_mode",
+            Action::PublishNote => "publish_note",
+            Action::NewLine => "new_line",
+            Action::RunCommand => "run_command",
+            Action::DeleteWord => "delete_word",
+            Action::WordBack => "word_back",
+            Action::WordForward => "word_forward",
+            Action::CursorUp => "cursor_up",
+            Action::CursorDown => "cursor_down",
+            Action::OpenNote => "open_note",
+            Action::Comment => "comment",
+            Action::React => "react",
+            Action::EditNote => "edit_note",
+            Action::DeleteNote => "delete_note",
+            Action::ToggleVote => "toggle_vote",
+        }
+    }
+
+    /// Short description shown in the help box
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit retro",
+            Action::ToggleHelp => "show/hide help",
+            Action::ToggleParticipants => "participants",
+            Action::ToggleDashboard => "dashboard",
+            Action::ToggleTrends => "trends",
+            Action::ToggleCompact => "wrap/cut off notes",
+            Action::InsertMode => "new note",
+            Action::CommandMode => "command mode",
+            Action::NormalMode => "normal mode",
+            Action::PublishNote => "create note",
+            Action::NewLine => "new line",
+            Action::RunCommand => "run command",
+            Action::DeleteWord => "delete word",
+            Action::WordBack => "word back",
+            Action::WordForward => "word forward",
+            Action::CursorUp => "previous note",
+            Action::CursorDown => "next note",
+            Action::OpenNote => "open note",
+            Action::Comment => "write comment",
+            Action::React => "react",
+            Action::EditNote => "edit",
+            Action::DeleteNote => "delete",
+            Action::ToggleVote => "vote/unvote",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == s)
+            .ok_or_else(|| anyhow!("unknown action `{s}`"))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A single key press, together with its modifiers
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// If the key types a character, rather than doing something
+    fn is_printable(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers;
+
+        // Shift is already part of the character, `?` is `?` no matter how
+        // the terminal reports it.
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+
+        KeyChord {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    /// Parse keys such as `q`, `?`, `esc`, `ctrl-c` or `alt-backspace`
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = s;
+
+        while let Some((modifier, rest)) = key.split_once('-').filter(|(_, rest)| !rest.is_empty())
+        {
+            modifiers |= match modifier {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => bail!("unknown modifier `{modifier}` in `{s}`"),
+            };
+            key = rest;
+        }
+
+        let code = match key {
+            "esc" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "backspace" => KeyCode::Backspace,
+            "tab" => KeyCode::Tab,
+            "space" => KeyCode::Char(' '),
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            key if key.starts_with('f') && key.len() > 1 => KeyCode::F(
+                key[1..]
+                    .parse()
+                    .map_err(|_| anyhow!("unknown key `{key}` in `{s}`"))?,
+            ),
+            key if key.chars().count() == 1 => KeyCode::Char(key.chars().next().unwrap()),
+            _ => bail!("unknown key `{key}` in `{s}`"),
+        };
+
+        Ok(KeyChord { code, modifiers })
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+
+        match self.code {
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Enter => write!(f, "↵"),
+            KeyCode::Backspace => write!(f, "⌫"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A sequence of keys that triggers an action in a mode
+pub struct Binding {
+    /// The mode the binding is active in
+    pub mode: Mode,
+
+    /// Keys to press, in order
+    pub keys: Vec<KeyChord>,
+
+    /// What happens when all keys were pressed
+    pub action: Action,
+}
+
+impl Binding {
+    fn new(mode: Mode, keys: &str, action: Action) -> Self {
+        Binding {
+            mode,
+            keys: parse_sequence(keys).expect("invalid default binding"),
+            action,
+        }
+    }
+
+    /// The keys of the binding, as shown in the help box
+    pub fn keys_label(&self) -> String {
+        self.keys
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+/// What a (partial) sequence of key presses resolved to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyResult {
+    /// The keys complete a binding
+    Action(Action),
+
+    /// The keys are the start of at least one binding, wait for more
+    Pending,
+
+    /// No binding matches
+    Unbound,
+}
+
+#[derive(Clone, Debug)]
+/// Maps key sequences to actions, per mode
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings = vec![
+            Binding::new(Mode::Normal, "q", Action::Quit),
+            Binding::new(Mode::Normal, "?", Action::ToggleHelp),
+            Binding::new(Mode::Normal, "p", Action::ToggleParticipants),
+            Binding::new(Mode::Normal, "d", Action::ToggleDashboard),
+            Binding::new(Mode::Normal, "t", Action::ToggleTrends),
+            Binding::new(Mode::Normal, "c", Action::ToggleCompact),
+            Binding::new(Mode::Normal, "i", Action::InsertMode),
+            Binding::new(Mode::Normal, ":", Action::CommandMode),
+            Binding::new(Mode::Normal, "k", Action::CursorUp),
+            Binding::new(Mode::Normal, "j", Action::CursorDown),
+            Binding::new(Mode::Normal, "enter", Action::OpenNote),
+            Binding::new(Mode::Normal, "r", Action::React),
+            Binding::new(Mode::Detail, "c", Action::Comment),
+            Binding::new(Mode::Detail, "r", Action::React),
+            Binding::new(Mode::Detail, "v", Action::ToggleVote),
+            Binding::new(Mode::Detail, "e", Action::EditNote),
+            Binding::new(Mode::Detail, "d d", Action::DeleteNote),
+            Binding::new(Mode::Detail, "?", Action::ToggleHelp),
+            Binding::new(Mode::Detail, "esc", Action::NormalMode),
+            Binding::new(Mode::Insert, "enter", Action::PublishNote),
+            Binding::new(Mode::Insert, "alt-enter", Action::NewLine),
+            Binding::new(Mode::Insert, "shift-enter", Action::NewLine),
+            Binding::new(Mode::Command, "enter", Action::RunCommand),
+        ];
+
+        for mode in [Mode::Insert, Mode::Command] {
+            bindings.extend([
+                Binding::new(mode.clone(), "f1", Action::ToggleHelp),
+                Binding::new(mode.clone(), "esc", Action::NormalMode),
+                Binding::new(mode.clone(), "alt-backspace", Action::DeleteWord),
+                Binding::new(mode.clone(), "alt-left", Action::WordBack),
+                Binding::new(mode.clone(), "alt-right", Action::WordForward),
+            ]);
+        }
+
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// The default keymap with the bindings from the config applied on top.
+    ///
+    /// Keys are action names, optionally scoped to a mode (`insert.publish_note`),
+    /// values are space separated key sequences (`ctrl-s`, `g g`). An unscoped
+    /// override applies to normal mode only. A single printable key can't be
+    /// bound in insert or command mode, it would no longer be typed.
+    pub fn with_overrides(overrides: &HashMap<String, String>) -> Result<Self> {
+        let mut keymap = Keymap::default();
+
+        for (name, keys) in overrides {
+            let (mode, action, scoped) = match name.split_once('.') {
+                Some((mode, action)) => (parse_mode(mode)?, action, true),
+                None => (Mode::Normal, name.as_str(), false),
+            };
+            let action: Action = action.parse()?;
+            let keys = parse_sequence(keys)?;
+
+            if matches!(mode, Mode::Insert | Mode::Command)
+                && keys.len() == 1
+                && keys[0].is_printable()
+            {
+                bail!("`{name}` can't be `{}`, it has to be typed", keys[0]);
+            }
+
+            let mut found = false;
+            for binding in keymap
+                .bindings
+                .iter_mut()
+                .filter(|binding| binding.action == action && binding.mode == mode)
+            {
+                binding.keys = keys.clone();
+                found = true;
+            }
+
+            match (scoped, found) {
+                (true, false) => keymap.bindings.push(Binding { mode, keys, action }),
+                (false, false) => {
+                    bail!("`{name}` has no default binding in normal mode, prefix it with a mode")
+                }
+                _ => {}
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// Resolve the keys pressed so far in the given mode
+    pub fn resolve(&self, mode: &Mode, keys: &[KeyChord]) -> KeyResult {
+        let mut result = KeyResult::Unbound;
+
+        for binding in self.bindings.iter().filter(|binding| binding.mode == *mode) {
+            if binding.keys == keys {
+                return KeyResult::Action(binding.action);
+            }
+            if binding.keys.starts_with(keys) {
+                result = KeyResult::Pending;
+            }
+        }
+
+        result
+    }
+
+    /// All bindings active in the given mode
+    pub fn bindings(&self, mode: &Mode) -> impl Iterator<Item = &Binding> {
+        let mode = mode.clone();
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.mode == mode)
+    }
+}
+
+fn parse_sequence(keys: &str) -> Result<Vec<KeyChord>> {
+    let keys = keys
+        .split_whitespace()
+        .map(KeyChord::from_str)
+        .collect::<Result<Vec<KeyChord>>>()?;
+
+    if keys.is_empty() {
+        bail!("empty key binding");
+    }
+
+    Ok(keys)
+}
+
+fn parse_mode(mode: &str) -> Result<Mode> {
+    match mode {
+        "normal" => Ok(Mode::Normal),
+        "insert" => Ok(Mode::Insert),
+        "command" => Ok(Mode::Command),
+        "detail" => Ok(Mode::Detail),
+        _ => bail!("unknown mode `{mode}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &str) -> Vec<KeyChord> {
+        parse_sequence(keys).unwrap()
+    }
+
+    fn overrides(pairs: &[(&str, &str)]) -> Result<Keymap> {
+        Keymap::with_overrides(
+            &pairs
+                .iter()
+                .map(|(name, keys)| (name.to_string(), keys.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn key_chords_parse() {
+        for (text, code, modifiers) in [
+            ("q", KeyCode::Char('q'), KeyModifiers::NONE),
+            ("space", KeyCode::Char(' '), KeyModifiers::NONE),
+            ("f1", KeyCode::F(1), KeyModifiers::NONE),
+            ("ctrl-c", KeyCode::Char('c'), KeyModifiers::CONTROL),
+            (
+                "ctrl-alt-backspace",
+                KeyCode::Backspace,
+                KeyModifiers::CONTROL | KeyModifiers::ALT,
+            ),
+            ("-", KeyCode::Char('-'), KeyModifiers::NONE),
+        ] {
+            assert_eq!(
+                text.parse::<KeyChord>().unwrap(),
+                KeyChord { code, modifiers },
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_bindings_are_rejected() {
+        assert!("hyper-x".parse::<KeyChord>().is_err());
+        assert!("fx".parse::<KeyChord>().is_err());
+        assert!(overrides(&[("quit", " ")]).is_err());
+        assert!(overrides(&[("fly", "x")]).is_err());
+        assert!(overrides(&[("visual.quit", "x")]).is_err());
+    }
+
+    #[test]
+    fn unscoped_overrides_only_change_normal_mode() {
+        let keymap = overrides(&[("toggle_help", "h")]).unwrap();
+
+        assert_eq!(
+            keymap.resolve(&Mode::Normal, &keys("h")),
+            KeyResult::Action(Action::ToggleHelp)
+        );
+        assert_eq!(
+            keymap.resolve(&Mode::Normal, &keys("?")),
+            KeyResult::Unbound
+        );
+        for mode in [Mode::Insert, Mode::Command] {
+            assert_eq!(keymap.resolve(&mode, &keys("h")), KeyResult::Unbound);
+            assert_eq!(
+                keymap.resolve(&mode, &keys("f1")),
+                KeyResult::Action(Action::ToggleHelp)
+            );
+        }
+        assert_eq!(
+            keymap.resolve(&Mode::Detail, &keys("?")),
+            KeyResult::Action(Action::ToggleHelp)
+        );
+    }
+
+    #[test]
+    fn unscoped_overrides_need_a_normal_mode_binding() {
+        assert!(overrides(&[("toggle_vote", "x")]).is_err());
+
+        let keymap = overrides(&[("detail.toggle_vote", "x")]).unwrap();
+        assert_eq!(
+            keymap.resolve(&Mode::Detail, &keys("x")),
+            KeyResult::Action(Action::ToggleVote)
+        );
+    }
+
+    #[test]
+    fn scoped_overrides_add_bindings() {
+        let keymap = overrides(&[("normal.edit_note", "g e")]).unwrap();
+
+        assert_eq!(
+            keymap.resolve(&Mode::Normal, &keys("g")),
+            KeyResult::Pending
+        );
+        assert_eq!(
+            keymap.resolve(&Mode::Normal, &keys("g e")),
+            KeyResult::Action(Action::EditNote)
+        );
+        assert_eq!(
+            keymap.resolve(&Mode::Detail, &keys("e")),
+            KeyResult::Action(Action::EditNote)
+        );
+    }
+
+    #[test]
+    fn printable_keys_stay_typeable() {
+        assert!(overrides(&[("insert.publish_note", "s")]).is_err());
+        assert!(overrides(&[("command.normal_mode", "shift-q")]).is_err());
+
+        for (name, keys) in [
+            ("insert.publish_note", "ctrl-s"),
+            ("command.normal_mode", "alt-q"),
+            ("insert.normal_mode", "j k"),
+        ] {
+            assert!(overrides(&[(name, keys)]).is_ok(), "{name} = {keys}");
+        }
+    }
+}
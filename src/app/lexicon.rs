@@ -0,0 +1,198 @@
+use std::{collections::HashMap, fmt::Debug, fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+
+use super::sentiment::Sentiment;
+
+/// The lexicon used when the user doesn't bring their own
+const DEFAULT_LEXICON: &str = include_str!("lexicon.txt");
+
+/// Words that flip the meaning of the words after them, "not good"
+const NEGATIONS: [&str; 20] = [
+    "not", "no", "never", "nothing", "without", "hardly", "cannot", "dont", "don't", "isnt",
+    "isn't", "wasnt", "wasn't", "aren't", "weren't", "didn't", "doesn't", "can't", "won't",
+    "couldn't",
+];
+
+/// How many words after a negation are flipped
+const NEGATION_SCOPE: usize = 3;
+
+/// Guesses the sentiment of a note from its text
+pub trait Classifier: Debug + Send + Sync {
+    fn classify(&self, text: &str) -> Sentiment;
+}
+
+#[derive(Clone, Debug)]
+/// Classifies notes by adding up the scores of the words and emoji in them
+pub struct Lexicon {
+    scores: HashMap<String, i32>,
+}
+
+impl Default for Lexicon {
+    fn default() -> Self {
+        Lexicon {
+            scores: parse(DEFAULT_LEXICON).expect("the built in lexicon is valid"),
+        }
+    }
+}
+
+impl Lexicon {
+    /// The built in lexicon, extended with the entries of the given file.
+    /// Entries in the file take precedence.
+    pub fn with_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("could not read lexicon {}", path.display()))?;
+        let scores =
+            parse(&contents).with_context(|| format!("invalid lexicon {}", path.display()))?;
+
+        let mut lexicon = Lexicon::default();
+        lexicon.scores.extend(scores);
+
+        Ok(lexicon)
+    }
+}
+
+impl Classifier for Lexicon {
+    fn classify(&self, text: &str) -> Sentiment {
+        let mut score = 0;
+        let mut negated = 0;
+
+        for token in tokens(text) {
+            if token.chars().all(|c| ".,;:!?".contains(c)) {
+                negated = 0;
+                continue;
+            }
+            if NEGATIONS.contains(&token.as_str()) {
+                negated = NEGATION_SCOPE;
+                continue;
+            }
+
+            let value = self.scores.get(&token).copied().unwrap_or(0);
+            if negated > 0 {
+                score -= value;
+                negated -= 1;
+            } else {
+                score += value;
+            }
+        }
+
+        match score {
+            score if score > 0 => Sentiment::Happy,
+            score if score < 0 => Sentiment::Sad,
+            _ => Sentiment::Neutral,
+        }
+    }
+}
+
+/// Split text into lowercased words, single emoji and punctuation
+fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '\'' {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+
+        // Variation selectors and joiners only change how an emoji looks
+        if !c.is_whitespace() && !matches!(c, '\u{fe0e}' | '\u{fe0f}' | '\u{200d}') {
+            tokens.push(c.to_string());
+        }
+    }
+
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+
+    tokens
+}
+
+/// Parse `word score` lines, blank lines and lines starting with `#` are
+/// skipped
+fn parse(contents: &str) -> Result<HashMap<String, i32>> {
+    let mut scores = HashMap::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let entry = line
+            .rsplit_once(char::is_whitespace)
+            .and_then(|(word, score)| Some((word.trim(), score.parse::<i32>().ok()?)));
+        let (word, score) =
+            entry.ok_or_else(|| anyhow!("line {}: expected `word score`", number + 1))?;
+
+        let word: String = word
+            .chars()
+            .filter(|c| !matches!(c, '\u{fe0e}' | '\u{fe0f}'))
+            .flat_map(char::to_lowercase)
+            .collect();
+        scores.insert(word, score);
+    }
+
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_classified_by_their_words() {
+        let lexicon = Lexicon::default();
+
+        for (text, sentiment) in [
+            ("good sprint", Sentiment::Happy),
+            ("deploys were painful", Sentiment::Sad),
+            ("we met on tuesday", Sentiment::Neutral),
+            ("", Sentiment::Neutral),
+            ("Great demo!", Sentiment::Happy),
+            ("release 🎉", Sentiment::Happy),
+            ("standups 😞", Sentiment::Sad),
+            ("fun but slow", Sentiment::Neutral),
+        ] {
+            assert_eq!(lexicon.classify(text), sentiment, "{text}");
+        }
+    }
+
+    #[test]
+    fn negations_flip_the_words_after_them() {
+        let lexicon = Lexicon::default();
+
+        for (text, sentiment) in [
+            ("not good", Sentiment::Sad),
+            ("isn't bad", Sentiment::Happy),
+            ("Isn't bad", Sentiment::Happy),
+            ("isnt bad", Sentiment::Happy),
+            ("not painful", Sentiment::Happy),
+            ("never slow", Sentiment::Happy),
+            ("don't love it", Sentiment::Sad),
+            // Only the next few words are flipped
+            ("not that it was really good", Sentiment::Happy),
+            // Punctuation ends the negation
+            ("not much, but good", Sentiment::Happy),
+            ("no. terrible", Sentiment::Sad),
+        ] {
+            assert_eq!(lexicon.classify(text), sentiment, "{text}");
+        }
+    }
+
+    #[test]
+    fn lexicon_files_parse() {
+        let scores = parse("# comment\n\nfirefighting -2\nmob  1\nWOW 3\n👍\u{fe0f} 1\n").unwrap();
+
+        assert_eq!(scores.get("firefighting"), Some(&-2));
+        assert_eq!(scores.get("mob"), Some(&1));
+        assert_eq!(scores.get("wow"), Some(&3));
+        assert_eq!(scores.get("👍"), Some(&1));
+        assert!(parse("firefighting").is_err());
+        assert!(parse("firefighting lots").is_err());
+    }
+}
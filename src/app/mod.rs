@@ -1,5 +1,17 @@
 pub mod command;
+pub mod comment;
+pub mod grouping;
+pub mod keymap;
+pub mod lexicon;
 pub mod mode;
 pub mod note;
+pub mod notification;
+pub mod participant;
+pub mod reaction;
+pub mod room;
 pub mod sentiment;
+pub mod sort;
 pub mod state;
+pub mod stats;
+pub mod text;
+pub mod theme;
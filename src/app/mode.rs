@@ -1,7 +1,5 @@
 use core::fmt;
 
-use tui::style::Color;
-
 /// The mode the retro app currently is in
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Mode {
@@ -13,17 +11,9 @@ pub enum Mode {
 
     /// Command mode - Group / vote / find
     Command,
-}
 
-impl Mode {
-    /// Get the highlight color for the note
-    pub fn get_color(&self) -> Color {
-        match self {
-            Self::Normal => Color::White,
-            Self::Insert => Color::Blue,
-            Self::Command => Color::Red,
-        }
-    }
+    /// Looking at a single note and its comments
+    Detail,
 }
 
 impl fmt::Display for Mode {
@@ -32,6 +22,7 @@ impl fmt::Display for Mode {
             Self::Normal => write!(f, " NOR "),
             Self::Insert => write!(f, " INS "),
             Self::Command => write!(f, " CMD "),
+            Self::Detail => write!(f, " DET "),
         }
     }
 }
@@ -1,10 +1,23 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use firestore_grpc::v1::{value::ValueType, Value};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use firestore_grpc::v1::{value::ValueType, ArrayValue, Document, MapValue, Value};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use unicode_width::UnicodeWidthStr;
 
-use super::sentiment::Sentiment;
+use super::{
+    lexicon::Classifier, reaction::Reaction, sentiment::Sentiment, stats::csv_field, text::truncate,
+};
 
-#[derive(Clone, Debug)]
+/// Longest note that can be published, in characters
+pub const MAX_LENGTH: usize = 280;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 /// A retro Note
 pub struct Note {
     /// Used for storing notes
@@ -19,46 +32,221 @@ pub struct Note {
     /// If the note was positive, negative or neutral
     pub sentiment: Sentiment,
 
+    /// If the sentiment was guessed from the text, rather than chosen by the
+    /// author with a smiley
+    pub inferred: bool,
+
     /// How many votes the note has received
     pub votes: u8,
+
+    /// Who voted for the note
+    pub voters: BTreeSet<String>,
+
+    /// How many comments have been written on the note
+    pub comments: u32,
+
+    /// Who reacted with what
+    pub reactions: BTreeMap<Reaction, BTreeSet<String>>,
+
+    /// Name of the group the note was put in, if any
+    pub group: Option<String>,
+
+    /// If the note was turned into an action item
+    pub action: bool,
+
+    /// If the action item was done
+    pub done: bool,
+
+    /// Unix timestamp (seconds) of when the note was published, set by the
+    /// backend
+    pub created_at: Option<i64>,
+
+    /// Unix timestamp (seconds) of the last change to the note, set by the
+    /// backend
+    pub updated_at: Option<i64>,
+
+    /// If the note was changed locally and the backend hasn't confirmed it
+    /// yet
+    #[serde(skip)]
+    pub pending: bool,
 }
 
 impl Note {
+    /// Header of the CSV export, in the same order as [`Note::to_csv`]
+    pub const CSV_HEADER: &'static str =
+        "author,text,sentiment,votes,group,action,done,created_at,updated_at";
+
     /// Create a new note, if the note contains a happy smiley it will be
     /// tagged with a happy sentiment, sad smiley will be negative and a
-    /// neutral smiley (or no smiley at all) will yield a neutral sentiment.
-    pub fn new(author: String, text: String) -> Self {
+    /// neutral smiley will be neutral. Without a smiley the classifier
+    /// guesses the sentiment from the text.
+    pub fn new(author: String, text: String, classifier: &dyn Classifier) -> Self {
+        let (sentiment, inferred) = Self::classify(&text, classifier);
+
         Note {
-            text: text.replace(":(", "").replace(":)", ""),
+            id: client_id(&author, &text),
+            text: text.replace(":(", "").replace(":)", "").replace(":|", ""),
             author,
-            id: text.clone(),
-            sentiment: Self::get_sentiment(&text),
+            sentiment,
+            inferred,
             votes: 0,
+            voters: BTreeSet::new(),
+            comments: 0,
+            reactions: BTreeMap::new(),
+            group: None,
+            action: false,
+            done: false,
+            created_at: None,
+            updated_at: None,
+            pending: false,
         }
     }
 
-    /// Get the sentiment for the note based on what emoji was used.
-    fn get_sentiment(text: &str) -> Sentiment {
+    /// Id of the note's document. Notes from the backend are known by their
+    /// full resource name, published notes by the client side id until the
+    /// backend confirms them, and this is the same for both.
+    pub fn document_id(&self) -> &str {
+        document_id(&self.id)
+    }
+
+    /// Reaction counts, such as `👍2 🎉1`
+    pub fn reaction_summary(&self) -> String {
+        self.reactions
+            .iter()
+            .map(|(reaction, users)| format!("{reaction}{}", users.len()))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// What's shown before the text in the notes list: author, sentiment,
+    /// action item and group
+    pub fn header(&self) -> String {
+        let (author, sentiment) = (truncate(&self.author, 8), self.sentiment_label());
+        let action = match (self.action, self.done) {
+            (true, true) => "☑ ",
+            (true, false) => "☐ ",
+            _ => "",
+        };
+        let group = match &self.group {
+            Some(group) => format!("[{group}] "),
+            None => "".to_string(),
+        };
+
+        // Pad by display width, `{:<8}` counts chars and misaligns wide ones
+        let padding = " ".repeat(8usize.saturating_sub(author.width()));
+
+        format!("{author}{padding} {sentiment} {action}{group}")
+    }
+
+    /// What's shown after the text in the notes list: votes, reactions and
+    /// comments
+    pub fn footer(&self) -> String {
+        let votes = if self.votes > 0 {
+            format!("[+{}]", self.votes)
+        } else {
+            "".to_string()
+        };
+        let reactions = self.reaction_summary();
+        let comments = if self.comments > 0 {
+            format!("💬{}", self.comments)
+        } else {
+            "".to_string()
+        };
+
+        [votes, reactions, comments]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Sentiment as shown next to the note, guessed sentiments get a `~`
+    /// instead of the eyes
+    pub fn sentiment_label(&self) -> String {
+        sentiment_label(self.sentiment, self.inferred)
+    }
+
+    /// The sentiment a note with the given text gets, and if it was guessed
+    /// rather than picked with a smiley
+    pub fn classify(text: &str, classifier: &dyn Classifier) -> (Sentiment, bool) {
+        match Self::get_sentiment(text) {
+            Some(sentiment) => (sentiment, false),
+            None => (classifier.classify(text), true),
+        }
+    }
+
+    /// The note as a line of CSV, timestamps are in RFC 3339 and UTC
+    pub fn to_csv(&self) -> String {
+        let time = |timestamp: Option<i64>| {
+            timestamp
+                .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+                .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default()
+        };
+
+        [
+            csv_field(&self.author),
+            csv_field(&self.text),
+            csv_field(&self.sentiment.to_string()),
+            self.votes.to_string(),
+            csv_field(self.group.as_deref().unwrap_or_default()),
+            self.action.to_string(),
+            self.done.to_string(),
+            time(self.created_at),
+            time(self.updated_at),
+        ]
+        .join(",")
+    }
+
+    /// Get the sentiment the author chose with a smiley, if any
+    fn get_sentiment(text: &str) -> Option<Sentiment> {
         if text.contains(":)") {
-            return Sentiment::Happy;
+            return Some(Sentiment::Happy);
         }
         if text.contains(":(") {
-            return Sentiment::Sad;
+            return Some(Sentiment::Sad);
+        }
+        if text.contains(":|") {
+            return Some(Sentiment::Neutral);
         }
-        Sentiment::Neutral
+        None
+    }
+}
+
+/// Id for a new note, picked by the client so publishing it again after a
+/// dropped connection doesn't store it twice
+/// A sentiment as shown on a note, guessed ones get a `~` for eyes
+pub fn sentiment_label(sentiment: Sentiment, inferred: bool) -> String {
+    let label = sentiment.to_string();
+
+    if inferred {
+        label.replacen(':', "~", 1)
+    } else {
+        label
     }
 }
 
+/// Last part of a resource name, ids without a path are returned as is
+pub fn document_id(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+fn client_id(author: &str, text: &str) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    Sha256::digest(format!("{author}\0{text}\0{now}").as_bytes())
+        .iter()
+        .take(10)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
 impl Display for Note {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (author, text, sentiment) = (&self.author, &self.text, &self.sentiment);
-        let votes = if self.votes > 0 {
-            format!("[+{}]", self.votes)
-        } else {
-            "".to_string()
-        };
-
-        write!(f, "{:<8} {sentiment} {text} {votes}", author)
+        write!(f, "{}{} {}", self.header(), self.text, self.footer())
     }
 }
 
@@ -88,6 +276,13 @@ impl Into<HashMap<String, Value>> for &Note {
             },
         );
 
+        fields.insert(
+            "inferred".to_string(),
+            Value {
+                value_type: Some(ValueType::BooleanValue(self.inferred)),
+            },
+        );
+
         fields.insert(
             "votes".to_string(),
             Value {
@@ -95,6 +290,80 @@ impl Into<HashMap<String, Value>> for &Note {
             },
         );
 
+        fields.insert(
+            "voters".to_string(),
+            Value {
+                value_type: Some(ValueType::ArrayValue(ArrayValue {
+                    values: self
+                        .voters
+                        .iter()
+                        .map(|voter| Value {
+                            value_type: Some(ValueType::StringValue(voter.clone())),
+                        })
+                        .collect(),
+                })),
+            },
+        );
+
+        fields.insert(
+            "comments".to_string(),
+            Value {
+                value_type: Some(ValueType::IntegerValue(self.comments.into())),
+            },
+        );
+
+        fields.insert(
+            "reactions".to_string(),
+            Value {
+                value_type: Some(ValueType::MapValue(MapValue {
+                    fields: self
+                        .reactions
+                        .iter()
+                        .map(|(reaction, users)| {
+                            let users = users
+                                .iter()
+                                .map(|user| Value {
+                                    value_type: Some(ValueType::StringValue(user.clone())),
+                                })
+                                .collect();
+
+                            (
+                                reaction.name().to_string(),
+                                Value {
+                                    value_type: Some(ValueType::ArrayValue(ArrayValue {
+                                        values: users,
+                                    })),
+                                },
+                            )
+                        })
+                        .collect(),
+                })),
+            },
+        );
+
+        fields.insert(
+            "action".to_string(),
+            Value {
+                value_type: Some(ValueType::BooleanValue(self.action)),
+            },
+        );
+
+        fields.insert(
+            "done".to_string(),
+            Value {
+                value_type: Some(ValueType::BooleanValue(self.done)),
+            },
+        );
+
+        if let Some(group) = &self.group {
+            fields.insert(
+                "group".to_string(),
+                Value {
+                    value_type: Some(ValueType::StringValue(group.clone())),
+                },
+            );
+        }
+
         fields
     }
 }
@@ -118,6 +387,67 @@ impl From<HashMap<String, Value>> for Note {
             _ => 0,
         };
 
+        let voters = match values.get("voters").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::ArrayValue(voters)) => voters
+                .values
+                .into_iter()
+                .filter_map(|voter| match voter.value_type {
+                    Some(ValueType::StringValue(voter)) => Some(voter),
+                    _ => None,
+                })
+                .collect(),
+            _ => BTreeSet::new(),
+        };
+
+        let comments: u32 = match values.get("comments").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::IntegerValue(comments)) => comments as u32,
+            _ => 0,
+        };
+
+        let reactions = match values.get("reactions").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::MapValue(map)) => map
+                .fields
+                .into_iter()
+                .filter_map(|(name, users)| {
+                    let reaction: Reaction = name.parse().ok()?;
+                    let users: BTreeSet<String> = match users.value_type {
+                        Some(ValueType::ArrayValue(users)) => users
+                            .values
+                            .into_iter()
+                            .filter_map(|user| match user.value_type {
+                                Some(ValueType::StringValue(user)) => Some(user),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => BTreeSet::new(),
+                    };
+
+                    (!users.is_empty()).then_some((reaction, users))
+                })
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+
+        let inferred = matches!(
+            values.get("inferred").and_then(|v| v.value_type.clone()),
+            Some(ValueType::BooleanValue(true))
+        );
+
+        let group = match values.get("group").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::StringValue(group)) if !group.is_empty() => Some(group),
+            _ => None,
+        };
+
+        let action = matches!(
+            values.get("action").and_then(|v| v.value_type.clone()),
+            Some(ValueType::BooleanValue(true))
+        );
+
+        let done = matches!(
+            values.get("done").and_then(|v| v.value_type.clone()),
+            Some(ValueType::BooleanValue(true))
+        );
+
         let sentiment = match values.get("sentiment").unwrap().value_type.clone().unwrap() {
             ValueType::StringValue(sentiment) => match sentiment.as_str() {
                 ":)" => Sentiment::Happy,
@@ -132,7 +462,55 @@ impl From<HashMap<String, Value>> for Note {
             text,
             author,
             sentiment,
+            inferred,
             votes,
+            voters,
+            comments,
+            reactions,
+            group,
+            action,
+            done,
+            created_at: None,
+            updated_at: None,
+            pending: false,
         }
     }
 }
+
+impl From<Document> for Note {
+    fn from(document: Document) -> Self {
+        let mut note: Note = document.fields.into();
+        note.id = document.name;
+        note.created_at = document.create_time.map(|time| time.seconds);
+        note.updated_at = document.update_time.map(|time| time.seconds);
+        note
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::lexicon::Lexicon;
+
+    #[test]
+    fn footer_shows_only_what_the_note_has() {
+        let mut note = Note::new("ann".to_string(), "hi".to_string(), &Lexicon::default());
+        assert_eq!(note.footer(), "");
+
+        note.comments = 2;
+        assert_eq!(note.footer(), "💬2");
+
+        note.votes = 3;
+        assert_eq!(note.footer(), "[+3] 💬2");
+
+        note.reactions
+            .insert(Reaction::Tada, BTreeSet::from(["bob".to_string()]));
+        assert_eq!(
+            note.footer(),
+            format!("[+3] {} 💬2", note.reaction_summary())
+        );
+
+        note.comments = 0;
+        assert_eq!(note.footer(), format!("[+3] {}", note.reaction_summary()));
+    }
+}
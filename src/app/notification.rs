@@ -0,0 +1,56 @@
+use std::{fmt::Display, time::Duration};
+
+use chrono::{DateTime, Local};
+
+/// How long a notification is shown as a toast, it stays in the `:messages`
+/// log after that
+pub const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// How serious a notification is
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug)]
+/// A message for the user, such as a failed request or a finished export
+pub struct Notification {
+    pub level: Level,
+
+    pub message: String,
+
+    /// When the notification was raised
+    pub time: DateTime<Local>,
+}
+
+impl Notification {
+    pub fn new(level: Level, message: String) -> Self {
+        Notification {
+            level,
+            message,
+            time: Local::now(),
+        }
+    }
+
+    /// If the notification is still shown as a toast
+    pub fn is_recent(&self) -> bool {
+        (Local::now() - self.time)
+            .to_std()
+            .is_ok_and(|elapsed| elapsed < TOAST_DURATION)
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        };
+
+        // Pad, so the levels line up in the `:messages` log
+        f.pad(label)
+    }
+}
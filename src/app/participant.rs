@@ -0,0 +1,159 @@
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::Utc;
+use firestore_grpc::v1::{value::ValueType, Value};
+
+/// Seconds without a heartbeat before a participant is shown as idle
+pub const IDLE_AFTER: i64 = 45;
+
+/// Seconds without a heartbeat before a participant is considered gone
+pub const OFFLINE_AFTER: i64 = 90;
+
+/// A new random session id, every run of the client gets its own
+pub fn session_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// What a participant is currently up to
+pub enum Presence {
+    /// Connected and active
+    Online,
+
+    /// Connected but hasn't touched the keyboard in a while
+    Idle,
+
+    /// Writing a new note
+    Typing,
+}
+
+impl Presence {
+    fn key(&self) -> &'static str {
+        match self {
+            Presence::Online => "online",
+            Presence::Idle => "idle",
+            Presence::Typing => "typing",
+        }
+    }
+}
+
+impl Display for Presence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rep = match self {
+            Presence::Online => "●",
+            Presence::Idle => "○",
+            Presence::Typing => "✎",
+        };
+
+        write!(f, "{}", rep)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Someone connected to the retro
+pub struct Participant {
+    /// Random id of the participant's session, used as the document id so
+    /// people with the same display name don't share one
+    pub id: String,
+
+    /// Name shown to the others
+    pub display_name: String,
+
+    /// Status as announced by the participant's own client
+    pub presence: Presence,
+
+    /// Unix timestamp (seconds) of the last heartbeat
+    pub last_seen: i64,
+}
+
+impl Participant {
+    /// Create a participant that was seen just now
+    pub fn new(id: String, display_name: String, presence: Presence) -> Self {
+        Participant {
+            id,
+            display_name,
+            presence,
+            last_seen: Utc::now().timestamp(),
+        }
+    }
+
+    /// Presence as seen by others, a participant that stopped sending
+    /// heartbeats is shown as idle regardless of what it last announced.
+    pub fn effective_presence(&self, now: i64) -> Presence {
+        if now - self.last_seen > IDLE_AFTER {
+            Presence::Idle
+        } else {
+            self.presence
+        }
+    }
+
+    /// If the participant stopped sending heartbeats long enough ago to be
+    /// considered gone.
+    pub fn is_gone(&self, now: i64) -> bool {
+        now - self.last_seen > OFFLINE_AFTER
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<HashMap<String, Value>> for &Participant {
+    fn into(self) -> HashMap<String, Value> {
+        let mut fields = HashMap::new();
+
+        fields.insert(
+            "display_name".to_string(),
+            Value {
+                value_type: Some(ValueType::StringValue(self.display_name.clone())),
+            },
+        );
+
+        fields.insert(
+            "presence".to_string(),
+            Value {
+                value_type: Some(ValueType::StringValue(self.presence.key().to_string())),
+            },
+        );
+
+        fields.insert(
+            "last_seen".to_string(),
+            Value {
+                value_type: Some(ValueType::IntegerValue(self.last_seen)),
+            },
+        );
+
+        fields
+    }
+}
+
+impl From<HashMap<String, Value>> for Participant {
+    fn from(values: HashMap<String, Value>) -> Self {
+        let display_name = match values
+            .get("display_name")
+            .and_then(|v| v.value_type.clone())
+        {
+            Some(ValueType::StringValue(name)) => name,
+            _ => "".to_string(),
+        };
+
+        let presence = match values.get("presence").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::StringValue(presence)) => match presence.as_str() {
+                "idle" => Presence::Idle,
+                "typing" => Presence::Typing,
+                _ => Presence::Online,
+            },
+            _ => Presence::Online,
+        };
+
+        let last_seen = match values.get("last_seen").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::IntegerValue(last_seen)) => last_seen,
+            _ => 0,
+        };
+
+        // The id is the name of the document, set by whoever read it
+        Participant {
+            id: "".to_string(),
+            display_name,
+            presence,
+            last_seen,
+        }
+    }
+}
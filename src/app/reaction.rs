@@ -0,0 +1,69 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+/// A lightweight reaction to a note, any participant can toggle each of them
+pub enum Reaction {
+    ThumbsUp,
+    Heart,
+    Joy,
+    Tada,
+    Thinking,
+}
+
+impl Reaction {
+    /// Every reaction, in the order they're shown in the picker
+    pub const ALL: [Reaction; 5] = [
+        Reaction::ThumbsUp,
+        Reaction::Heart,
+        Reaction::Joy,
+        Reaction::Tada,
+        Reaction::Thinking,
+    ];
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Reaction::ThumbsUp => "👍",
+            Reaction::Heart => "❤️",
+            Reaction::Joy => "😂",
+            Reaction::Tada => "🎉",
+            Reaction::Thinking => "🤔",
+        }
+    }
+
+    /// Name used when storing the reaction, and accepted by `:react`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reaction::ThumbsUp => "thumbsup",
+            Reaction::Heart => "heart",
+            Reaction::Joy => "joy",
+            Reaction::Tada => "tada",
+            Reaction::Thinking => "thinking",
+        }
+    }
+}
+
+impl Display for Reaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.emoji())
+    }
+}
+
+impl FromStr for Reaction {
+    type Err = anyhow::Error;
+
+    /// Parse either the emoji itself (with or without the emoji variation
+    /// selector) or its name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim_end_matches('\u{fe0f}');
+
+        Reaction::ALL
+            .into_iter()
+            .find(|reaction| {
+                reaction.name() == s || reaction.emoji().trim_end_matches('\u{fe0f}') == s
+            })
+            .ok_or_else(|| anyhow!("unknown reaction `{s}`"))
+    }
+}
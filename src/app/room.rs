@@ -0,0 +1,342 @@
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::Utc;
+use clap::ValueEnum;
+use firestore_grpc::v1::{value::ValueType, Value};
+use sha2::{Digest, Sha256};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+/// The format of a retro
+pub enum Template {
+    /// Free form notes, tagged with a sentiment
+    #[default]
+    Default,
+
+    /// Start doing, stop doing, continue doing
+    StartStopContinue,
+
+    /// Mad, sad, glad
+    MadSadGlad,
+
+    /// Liked, learned, lacked, longed for
+    FourLs,
+}
+
+impl Template {
+    fn key(&self) -> &'static str {
+        match self {
+            Template::Default => "default",
+            Template::StartStopContinue => "start-stop-continue",
+            Template::MadSadGlad => "mad-sad-glad",
+            Template::FourLs => "four-ls",
+        }
+    }
+
+    fn from_key(key: &str) -> Self {
+        match key {
+            "start-stop-continue" => Template::StartStopContinue,
+            "mad-sad-glad" => Template::MadSadGlad,
+            "four-ls" => Template::FourLs,
+            _ => Template::Default,
+        }
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A retro room and its settings
+pub struct Room {
+    /// Shareable id, used to join the room
+    pub id: String,
+
+    /// Human readable name
+    pub name: String,
+
+    /// What kind of retro this is
+    pub template: Template,
+
+    /// How many notes each participant may vote for, None for unlimited
+    pub vote_budget: Option<u8>,
+
+    /// If true, nobody gets to see who voted for what
+    pub anonymous: bool,
+
+    /// Sha256 hex digest of the salt followed by the password, if the room
+    /// has one. Only the client checks it, so it keeps people out of the
+    /// room by accident but doesn't protect what's in it.
+    pub password_hash: Option<String>,
+
+    /// Random hex string hashed with the password, empty for rooms created
+    /// before passwords were salted
+    pub password_salt: String,
+
+    /// Name of the series of retros the room belongs to, such as the
+    /// team's sprint retros
+    pub series: Option<String>,
+
+    /// Archived rooms are kept around but can't be joined
+    pub archived: bool,
+
+    /// Unix timestamp (seconds) of when the room was created
+    pub created_at: i64,
+}
+
+impl Room {
+    /// Create a new room, the id is assigned by the backend once it's stored.
+    pub fn new(
+        name: String,
+        template: Template,
+        vote_budget: Option<u8>,
+        password: Option<&str>,
+        series: Option<String>,
+    ) -> Self {
+        let password_salt = match password {
+            Some(_) => hex(&rand::random::<[u8; 16]>()),
+            None => "".to_string(),
+        };
+
+        Room {
+            id: "".to_string(),
+            name,
+            template,
+            vote_budget,
+            anonymous: false,
+            password_hash: password.map(|password| Self::hash_password(&password_salt, password)),
+            password_salt,
+            series,
+            archived: false,
+            created_at: Utc::now().timestamp(),
+        }
+    }
+
+    /// If the given password lets you into the room. Rooms without a
+    /// password can be joined by anyone.
+    pub fn accepts(&self, password: Option<&str>) -> bool {
+        match (&self.password_hash, password) {
+            (None, _) => true,
+            (Some(hash), Some(password)) => {
+                *hash == Self::hash_password(&self.password_salt, password)
+            }
+            (Some(_), None) => false,
+        }
+    }
+
+    fn hash_password(salt: &str, password: &str) -> String {
+        hex(&Sha256::digest(format!("{salt}{password}").as_bytes()))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<HashMap<String, Value>> for &Room {
+    fn into(self) -> HashMap<String, Value> {
+        let mut fields = HashMap::new();
+
+        fields.insert(
+            "name".to_string(),
+            Value {
+                value_type: Some(ValueType::StringValue(self.name.clone())),
+            },
+        );
+
+        fields.insert(
+            "template".to_string(),
+            Value {
+                value_type: Some(ValueType::StringValue(self.template.key().to_string())),
+            },
+        );
+
+        fields.insert(
+            "vote_budget".to_string(),
+            Value {
+                value_type: Some(match self.vote_budget {
+                    Some(budget) => ValueType::IntegerValue(budget.into()),
+                    None => ValueType::NullValue(0),
+                }),
+            },
+        );
+
+        fields.insert(
+            "anonymous".to_string(),
+            Value {
+                value_type: Some(ValueType::BooleanValue(self.anonymous)),
+            },
+        );
+
+        fields.insert(
+            "password_hash".to_string(),
+            Value {
+                value_type: Some(match &self.password_hash {
+                    Some(hash) => ValueType::StringValue(hash.clone()),
+                    None => ValueType::NullValue(0),
+                }),
+            },
+        );
+
+        fields.insert(
+            "password_salt".to_string(),
+            Value {
+                value_type: Some(ValueType::StringValue(self.password_salt.clone())),
+            },
+        );
+
+        fields.insert(
+            "series".to_string(),
+            Value {
+                value_type: Some(match &self.series {
+                    Some(series) => ValueType::StringValue(series.clone()),
+                    None => ValueType::NullValue(0),
+                }),
+            },
+        );
+
+        fields.insert(
+            "archived".to_string(),
+            Value {
+                value_type: Some(ValueType::BooleanValue(self.archived)),
+            },
+        );
+
+        fields.insert(
+            "created_at".to_string(),
+            Value {
+                value_type: Some(ValueType::IntegerValue(self.created_at)),
+            },
+        );
+
+        fields
+    }
+}
+
+impl From<HashMap<String, Value>> for Room {
+    fn from(values: HashMap<String, Value>) -> Self {
+        let name = match values.get("name").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::StringValue(name)) => name,
+            _ => "".to_string(),
+        };
+
+        let template = match values.get("template").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::StringValue(template)) => Template::from_key(&template),
+            _ => Template::Default,
+        };
+
+        let vote_budget = match values.get("vote_budget").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::IntegerValue(budget)) => Some(budget as u8),
+            _ => None,
+        };
+
+        let anonymous = matches!(
+            values.get("anonymous").and_then(|v| v.value_type.clone()),
+            Some(ValueType::BooleanValue(true))
+        );
+
+        let password_hash = match values
+            .get("password_hash")
+            .and_then(|v| v.value_type.clone())
+        {
+            Some(ValueType::StringValue(hash)) => Some(hash),
+            _ => None,
+        };
+
+        let password_salt = match values
+            .get("password_salt")
+            .and_then(|v| v.value_type.clone())
+        {
+            Some(ValueType::StringValue(salt)) => salt,
+            _ => "".to_string(),
+        };
+
+        let series = match values.get("series").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::StringValue(series)) => Some(series),
+            _ => None,
+        };
+
+        let archived = matches!(
+            values.get("archived").and_then(|v| v.value_type.clone()),
+            Some(ValueType::BooleanValue(true))
+        );
+
+        let created_at = match values.get("created_at").and_then(|v| v.value_type.clone()) {
+            Some(ValueType::IntegerValue(created_at)) => created_at,
+            _ => 0,
+        };
+
+        Room {
+            id: "".to_string(),
+            name,
+            template,
+            vote_budget,
+            anonymous,
+            password_hash,
+            password_salt,
+            series,
+            archived,
+            created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(password: Option<&str>) -> Room {
+        Room::new("retro".to_string(), Template::Default, None, password, None)
+    }
+
+    #[test]
+    fn rooms_without_a_password_let_everyone_in() {
+        let room = room(None);
+
+        assert!(room.password_hash.is_none());
+        assert!(room.accepts(None));
+        assert!(room.accepts(Some("anything")));
+    }
+
+    #[test]
+    fn rooms_with_a_password_need_the_right_one() {
+        let room = room(Some("hunter2"));
+
+        assert!(room.accepts(Some("hunter2")));
+        assert!(!room.accepts(Some("hunter3")));
+        assert!(!room.accepts(Some("")));
+        assert!(!room.accepts(None));
+    }
+
+    #[test]
+    fn passwords_are_salted() {
+        let (a, b) = (room(Some("hunter2")), room(Some("hunter2")));
+
+        assert_eq!(a.password_salt.len(), 32);
+        assert_ne!(a.password_salt, b.password_salt);
+        assert_ne!(a.password_hash, b.password_hash);
+        assert_ne!(a.password_hash, Some(Room::hash_password("", "hunter2")));
+    }
+
+    #[test]
+    fn rooms_from_before_salting_still_accept_their_password() {
+        let mut room = room(None);
+        room.password_hash = Some(Room::hash_password("", "hunter2"));
+
+        assert!(room.accepts(Some("hunter2")));
+        assert!(!room.accepts(Some("hunter3")));
+    }
+
+    #[test]
+    fn the_salt_is_stored_with_the_room() {
+        let room = room(Some("hunter2"));
+        let fields: HashMap<String, Value> = (&room).into();
+        let stored = Room::from(fields);
+
+        assert_eq!(stored.password_salt, room.password_salt);
+        assert!(stored.accepts(Some("hunter2")));
+    }
+}
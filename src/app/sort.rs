@@ -0,0 +1,249 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{note::Note, sentiment::Sentiment};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// What the notes list is ordered by
+pub enum SortKey {
+    Votes,
+    Created,
+    Author,
+    Sentiment,
+    Group,
+}
+
+impl SortKey {
+    const ALL: [SortKey; 5] = [
+        SortKey::Votes,
+        SortKey::Created,
+        SortKey::Author,
+        SortKey::Sentiment,
+        SortKey::Group,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            SortKey::Votes => "votes",
+            SortKey::Created => "created",
+            SortKey::Author => "author",
+            SortKey::Sentiment => "sentiment",
+            SortKey::Group => "group",
+        }
+    }
+
+    /// Most votes and newest notes go first, everything else is alphabetical
+    fn descending_by_default(&self) -> bool {
+        matches!(self, SortKey::Votes | SortKey::Created)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// How the notes list is ordered
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Sort {
+            key: SortKey::Votes,
+            descending: true,
+        }
+    }
+}
+
+impl Sort {
+    /// Parse the arguments of the sort command, such as `author` or
+    /// `votes asc`
+    pub fn from_args(args: &[&str]) -> Result<Self> {
+        let key: SortKey = args
+            .first()
+            .ok_or_else(|| anyhow!("sort by what?"))?
+            .parse()?;
+        let descending = match args.get(1) {
+            None => key.descending_by_default(),
+            Some(&"asc") => false,
+            Some(&"desc") => true,
+            Some(direction) => bail!("unknown direction `{direction}`, use asc or desc"),
+        };
+
+        Ok(Sort { key, descending })
+    }
+
+    /// Sort the notes in place. Notes that compare equal are ordered by when
+    /// they were written and then by id, so the order doesn't change between
+    /// updates.
+    pub fn apply(&self, notes: &mut [Note]) {
+        notes.sort_by(|a, b| {
+            let ordering = match (self.key, &a.group, &b.group) {
+                // Notes that aren't in a group go last, whichever the direction
+                (SortKey::Group, Some(_), None) => Ordering::Less,
+                (SortKey::Group, None, Some(_)) => Ordering::Greater,
+                _ if self.descending => self.compare(a, b).reverse(),
+                _ => self.compare(a, b),
+            };
+
+            ordering
+                .then_with(|| created(a).cmp(&created(b)))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+    }
+
+    fn compare(&self, a: &Note, b: &Note) -> Ordering {
+        match self.key {
+            SortKey::Votes => a.votes.cmp(&b.votes),
+            SortKey::Created => created(a).cmp(&created(b)),
+            SortKey::Author => a.author.to_lowercase().cmp(&b.author.to_lowercase()),
+            SortKey::Sentiment => sentiment_rank(a.sentiment).cmp(&sentiment_rank(b.sentiment)),
+            SortKey::Group => a
+                .group
+                .as_ref()
+                .map(|group| group.to_lowercase())
+                .cmp(&b.group.as_ref().map(|group| group.to_lowercase())),
+        }
+    }
+}
+
+/// When the note was written. Notes the backend hasn't stored yet were
+/// written just now, they're newer than any stored note.
+fn created(note: &Note) -> i64 {
+    note.created_at.unwrap_or(i64::MAX)
+}
+
+fn sentiment_rank(sentiment: Sentiment) -> u8 {
+    match sentiment {
+        Sentiment::Happy => 0,
+        Sentiment::Neutral => 1,
+        Sentiment::Sad => 2,
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        SortKey::ALL
+            .into_iter()
+            .find(|key| key.name() == s)
+            .ok_or_else(|| anyhow!("unknown sort key `{s}`"))
+    }
+}
+
+impl Display for Sort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arrow = if self.descending { "↓" } else { "↑" };
+
+        write!(f, "{} {arrow}", self.key.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::lexicon::Lexicon;
+
+    fn note(id: &str, author: &str, votes: u8, created_at: i64) -> Note {
+        let mut note = Note::new(author.to_string(), id.to_string(), &Lexicon::default());
+        note.id = id.to_string();
+        note.votes = votes;
+        note.created_at = Some(created_at);
+        note
+    }
+
+    fn ids(notes: &[Note]) -> Vec<&str> {
+        notes.iter().map(|note| note.id.as_str()).collect()
+    }
+
+    #[test]
+    fn from_args_uses_the_default_direction_of_the_key() {
+        assert_eq!(
+            Sort::from_args(&["votes"]).unwrap(),
+            Sort {
+                key: SortKey::Votes,
+                descending: true
+            }
+        );
+        assert_eq!(
+            Sort::from_args(&["author", "desc"]).unwrap(),
+            Sort {
+                key: SortKey::Author,
+                descending: true
+            }
+        );
+    }
+
+    #[test]
+    fn from_args_rejects_missing_and_unknown_arguments() {
+        assert_eq!(
+            Sort::from_args(&[]).unwrap_err().to_string(),
+            "sort by what?"
+        );
+        assert_eq!(
+            Sort::from_args(&["color"]).unwrap_err().to_string(),
+            "unknown sort key `color`"
+        );
+        assert_eq!(
+            Sort::from_args(&["votes", "up"]).unwrap_err().to_string(),
+            "unknown direction `up`, use asc or desc"
+        );
+    }
+
+    #[test]
+    fn equal_keys_are_ordered_by_creation_then_id() {
+        let sort = Sort::from_args(&["votes"]).unwrap();
+        let mut notes = vec![
+            note("d", "ann", 1, 20),
+            note("c", "bob", 1, 10),
+            note("b", "ann", 3, 30),
+            note("a", "bob", 1, 20),
+        ];
+
+        sort.apply(&mut notes);
+        assert_eq!(ids(&notes), vec!["b", "c", "a", "d"]);
+
+        // Whatever order they come in, equal notes end up in the same place
+        notes.reverse();
+        sort.apply(&mut notes);
+        assert_eq!(ids(&notes), vec!["b", "c", "a", "d"]);
+    }
+
+    #[test]
+    fn unsaved_notes_are_the_newest() {
+        let mut notes = vec![
+            note("a", "ann", 0, 10),
+            note("b", "ann", 0, 30),
+            note("c", "ann", 0, 20),
+        ];
+        notes[0].created_at = None;
+
+        Sort::from_args(&["created"]).unwrap().apply(&mut notes);
+        assert_eq!(ids(&notes), vec!["a", "b", "c"]);
+
+        Sort::from_args(&["created", "asc"])
+            .unwrap()
+            .apply(&mut notes);
+        assert_eq!(ids(&notes), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn ungrouped_notes_go_last_in_both_directions() {
+        let mut notes = vec![
+            note("a", "ann", 0, 10),
+            note("b", "ann", 0, 20),
+            note("c", "ann", 0, 30),
+        ];
+        notes[1].group = Some("beta".to_string());
+        notes[2].group = Some("alpha".to_string());
+
+        Sort::from_args(&["group"]).unwrap().apply(&mut notes);
+        assert_eq!(ids(&notes), vec!["c", "b", "a"]);
+
+        Sort::from_args(&["group", "desc"])
+            .unwrap()
+            .apply(&mut notes);
+        assert_eq!(ids(&notes), vec!["b", "c", "a"]);
+    }
+}
@@ -1,17 +1,72 @@
-use std::{collections::HashSet, fmt::Debug, sync::mpsc::Sender};
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use crate::{app::mode::Mode, app::note::Note, cli::RetroArgs, network::actions::NetworkAction};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use crossterm::event::KeyEvent;
+use tokio::sync::mpsc::UnboundedSender;
 
-use super::sentiment::Sentiment;
+use crate::{
+    app::mode::Mode,
+    app::note::{document_id, Note},
+    network::{
+        actions::{
+            ActionItemChange, DoneChange, GroupChange, NetworkAction, NoteEdit, ReactionChange,
+        },
+        journal::Journal,
+    },
+};
+
+use super::{
+    comment::Comment,
+    grouping::suggest_groups,
+    keymap::Keymap,
+    lexicon::{Classifier, Lexicon},
+    notification::{Level, Notification},
+    participant::{session_id, Participant, Presence, IDLE_AFTER},
+    reaction::Reaction,
+    room::Room,
+    sentiment::Sentiment,
+    sort::Sort,
+    stats::RoomStats,
+    theme::Theme,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// What's on screen that changes as time passes, without anything happening
+struct ClockView {
+    toasts: usize,
+
+    /// Only counts when notes show how long ago they were written
+    minute: i64,
+
+    presences: Vec<Presence>,
+}
+
+/// How many notifications the `:messages` log keeps
+const MAX_NOTIFICATIONS: usize = 100;
+
+/// How many toasts are stacked on top of each other at most
+const MAX_TOASTS: usize = 3;
+
+/// How often the client tells the others it is still around
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
 
 #[derive(Clone, Debug)]
 /// Application state
 pub struct State {
-    /// what row is selected (used by the vote/group modes)
-    pub selected_rows: Vec<usize>,
+    /// Ids of the notes selected in command mode, they're turned into
+    /// indices when needed so live updates can't move the selection
+    selected: Vec<String>,
 
-    /// List of participants (display_names)
-    pub participants: HashSet<String>,
+    /// Everyone currently connected to the room
+    pub participants: Vec<Participant>,
 
     /// if in filter mode display only the notes matching this filter
     pub filter: Option<Sentiment>,
@@ -22,95 +77,806 @@ pub struct State {
     /// List of all notes, by any author
     pub notes: Vec<Note>,
 
+    /// How the notes are ordered
+    pub sort: Sort,
+
+    /// How many notes have been fetched so far, while the notes are loading
+    pub loading_notes: Option<usize>,
+
+    /// Index of the note under the cursor in normal mode
+    pub cursor: usize,
+
+    /// Id of the note shown in the detail popup
+    pub open_note: Option<String>,
+
+    /// Comments on the open note, oldest first
+    pub comments: Vec<Comment>,
+
+    /// Id of the note being edited in insert mode
+    pub editing: Option<String>,
+
+    /// If true, the reaction picker is shown for the note under the cursor
+    pub show_reaction_picker: bool,
+
+    /// Suggested groups waiting to be reviewed, as note ids. The first one is
+    /// the one in the command box.
+    pub suggestions: Vec<Vec<String>>,
+
     // A set of ids for the notes the current user has voted for
     my_votes: HashSet<String>,
 
+    /// How many notes the current user may vote for, None for unlimited
+    pub vote_budget: Option<u8>,
+
+    /// If true, who voted for what is not shown
+    pub anonymous: bool,
+
     /// If true, a box with a list of shorcuts for the active mode will be shown
     pub show_help: bool,
 
+    /// If true, the participant sidebar will be shown
+    pub show_participants: bool,
+
+    /// If true, the sentiment dashboard will be shown next to the notes
+    pub show_dashboard: bool,
+
+    /// If true, long notes are cut off instead of wrapped
+    pub compact: bool,
+
+    /// If true, the notes list shows how long ago each note was written
+    pub relative_time: bool,
+
+    /// If true, the trends of the room's series will be shown
+    pub show_trends: bool,
+
+    /// Id of the room the retro is held in
+    pub room_id: String,
+
+    /// Directory `:export` writes to, the working directory if None
+    pub export_dir: Option<PathBuf>,
+
+    /// Series the room belongs to, if any
+    pub series: Option<String>,
+
+    /// Stats of every retro in the series, oldest first
+    pub trends: Vec<RoomStats>,
+
+    /// Errors, warnings and other messages for the user, oldest first
+    pub notifications: Vec<Notification>,
+
+    /// If true, every past notification will be shown
+    pub show_messages: bool,
+
     /// Display name of the current user
     pub display_name: String,
 
-    sender: Sender<NetworkAction>,
+    /// Id of this session in the participant list
+    pub session_id: String,
+
+    /// What the keys do
+    pub keymap: Keymap,
+
+    /// Keys pressed so far of a multi-key binding, kept as they were
+    /// pressed so they can be typed if the sequence is abandoned
+    pub pending_keys: Vec<KeyEvent>,
+
+    /// Colors used by the widgets
+    pub theme: Theme,
 
-    /// Tick count, decides when to redraw the ui
-    pub tick_count: usize,
+    /// Changes waiting for the connection to come back
+    pub journal: Journal,
+
+    /// If the backend couldn't be reached the last time it was tried
+    pub offline: bool,
+
+    /// Guesses the sentiment of new notes without a smiley
+    pub classifier: Arc<dyn Classifier>,
+
+    sender: UnboundedSender<NetworkAction>,
+
+    // Set when the state changed without the UI being woken up, such as
+    // while the notes are loading
+    redraw: bool,
+
+    // The parts of the screen that change with time alone, as of the last
+    // tick
+    clock_view: ClockView,
+
+    // When the last heartbeat was sent, and with what presence
+    last_heartbeat: Instant,
+    announced_presence: Presence,
+
+    // When the user last pressed a key, used to detect idleness
+    last_input: Instant,
 }
 
 impl State {
-    pub fn new(sender: Sender<NetworkAction>, args: RetroArgs) -> Self {
+    pub fn new(sender: UnboundedSender<NetworkAction>, display_name: String) -> Self {
         State {
-            selected_rows: vec![],
-            participants: HashSet::new(),
+            selected: vec![],
+            participants: vec![],
             filter: None,
             mode: Mode::Normal,
             notes: vec![],
+            sort: Sort::default(),
+            loading_notes: None,
+            cursor: 0,
+            open_note: None,
+            comments: vec![],
+            editing: None,
+            show_reaction_picker: false,
+            suggestions: vec![],
             my_votes: HashSet::new(),
+            vote_budget: None,
+            anonymous: false,
             show_help: false,
-            display_name: args.display_name,
+            show_participants: false,
+            show_dashboard: false,
+            compact: false,
+            relative_time: false,
+            show_trends: false,
+            room_id: String::new(),
+            export_dir: None,
+            series: None,
+            trends: vec![],
+            notifications: vec![],
+            show_messages: false,
+            display_name,
+            session_id: session_id(),
+            keymap: Keymap::default(),
+            pending_keys: vec![],
+            theme: Theme::default(),
+            journal: Journal::default(),
+            offline: false,
+            classifier: Arc::new(Lexicon::default()),
             sender,
-            tick_count: 0,
+            redraw: false,
+            clock_view: ClockView::default(),
+            last_heartbeat: Instant::now(),
+            announced_presence: Presence::Online,
+            last_input: Instant::now(),
         }
     }
 
     pub fn dispatch(&mut self, action: NetworkAction) {
         if let Err(e) = self.sender.send(action) {
-            println!("error {}", e);
+            self.notify(
+                Level::Error,
+                format!("lost the connection to the network: {e}"),
+            );
+        }
+    }
+
+    /// Tell the user something, it's shown as a toast for a few seconds and
+    /// kept in the `:messages` log
+    pub fn notify(&mut self, level: Level, message: impl Into<String>) {
+        self.notifications
+            .push(Notification::new(level, message.into()));
+
+        if self.notifications.len() > MAX_NOTIFICATIONS {
+            self.notifications.remove(0);
         }
     }
 
+    /// The notifications that are shown as toasts, newest last
+    pub fn toasts(&self) -> Vec<&Notification> {
+        let recent: Vec<&Notification> = self
+            .notifications
+            .iter()
+            .filter(|notification| notification.is_recent())
+            .collect();
+
+        recent[recent.len().saturating_sub(MAX_TOASTS)..].to_vec()
+    }
+
     pub fn add_note(&mut self, note: Note) {
         self.dispatch(NetworkAction::PublishNote(note));
     }
 
-    pub fn set_notes(&mut self, notes: Vec<Note>) {
-        self.notes = notes;
+    /// Apply the settings of the room that was joined
+    pub fn set_room(&mut self, room: &Room) {
+        self.vote_budget = room.vote_budget;
+        self.anonymous = room.anonymous;
+        self.series = room.series.clone();
+    }
+
+    /// Write every note to `<room id>.csv` in the export directory and
+    /// return where it went
+    pub fn export(&mut self) -> Result<PathBuf> {
+        let path = self
+            .export_dir
+            .clone()
+            .unwrap_or_default()
+            .join(format!("{}.csv", self.room_id));
+
+        let mut csv = vec![Note::CSV_HEADER.to_string()];
+        csv.extend(self.notes.iter().map(Note::to_csv));
+        fs::write(&path, csv.join("\n") + "\n")
+            .with_context(|| format!("could not write {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    /// How many more notes the current user may vote for
+    pub fn votes_left(&self) -> Option<usize> {
+        self.vote_budget
+            .map(|budget| (budget as usize).saturating_sub(self.my_votes.len()))
+    }
+
+    pub fn set_notes(&mut self, mut notes: Vec<Note>) {
+        // Local changes the backend hasn't confirmed yet win over its copy,
+        // unless it's a published note: then the backend's copy shows it got
+        // through
+        for local in self.notes.iter().filter(|note| note.pending) {
+            match notes
+                .iter_mut()
+                .find(|note| note.document_id() == local.document_id())
+            {
+                Some(note) if note.id != local.id => {}
+                Some(note) => *note = local.clone(),
+                None => notes.push(local.clone()),
+            }
+        }
+
+        // Votes from an earlier session count towards the budget too
+        self.my_votes = notes
+            .iter()
+            .filter(|note| note.voters.contains(&self.display_name))
+            .map(|note| note.id.clone())
+            .collect();
+
+        let under_cursor = self.note_under_cursor();
+        let previous = std::mem::replace(&mut self.notes, notes);
+
+        // While a command is typed the notes shown keep their place, and new
+        // ones go at the end
+        if self.mode == Mode::Command {
+            self.sort.apply(&mut self.notes);
+            self.notes.sort_by_key(|note| {
+                previous
+                    .iter()
+                    .position(|shown| shown.id == note.id)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        self.sort_notes(under_cursor);
+    }
+
+    /// Order the notes differently, the cursor stays on the same note
+    pub fn set_sort(&mut self, sort: Sort) {
+        let under_cursor = self.note_under_cursor();
+        self.sort = sort;
+        self.sort.apply(&mut self.notes);
+        self.keep_cursor_on(under_cursor);
+    }
+
+    /// Sort the notes again, once they no longer have to stay in place for
+    /// a command
+    pub fn resort(&mut self) {
+        self.set_sort(self.sort);
+    }
+
+    fn note_under_cursor(&self) -> Option<String> {
+        self.notes.get(self.cursor).map(|note| note.id.clone())
+    }
+
+    /// Sort the notes, unless a command is being typed: the indices in it
+    /// have to keep pointing at the notes they were typed for
+    fn sort_notes(&mut self, under_cursor: Option<String>) {
+        if self.mode != Mode::Command {
+            self.sort.apply(&mut self.notes);
+        }
+        self.keep_cursor_on(under_cursor);
+    }
+
+    fn keep_cursor_on(&mut self, under_cursor: Option<String>) {
+        self.cursor = under_cursor
+            .and_then(|id| self.notes.iter().position(|note| note.id == id))
+            .unwrap_or(self.cursor)
+            .min(self.notes.len().saturating_sub(1));
+    }
+
+    /// Move the cursor up (negative) or down (positive) the notes list
+    pub fn move_cursor(&mut self, delta: isize) {
+        let last = self.notes.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// Show the note under the cursor in the detail popup
+    pub fn open_selected_note(&mut self) {
+        if let Some(note) = self.notes.get(self.cursor) {
+            let id = note.id.clone();
+            self.open_note = Some(id.clone());
+            self.comments = vec![];
+            self.mode = Mode::Detail;
+            self.dispatch(NetworkAction::GetComments(id));
+        }
+    }
+
+    pub fn close_note(&mut self) {
+        self.open_note = None;
+        self.comments = vec![];
+    }
+
+    /// The note shown in the detail popup
+    pub fn get_open_note(&self) -> Option<&Note> {
+        let id = self.open_note.as_ref()?;
+        self.notes.iter().find(|note| note.id == *id)
+    }
+
+    pub fn set_comments(&mut self, note_id: &str, comments: Vec<Comment>) {
+        if self.open_note.as_deref() == Some(note_id) {
+            self.comments = comments;
+        }
+    }
+
+    /// Comment on the open note
+    pub fn add_comment(&mut self, text: String) {
+        if let Some(note) = self.get_open_note() {
+            let note = note.clone();
+            let comment = Comment::new(self.display_name.clone(), text);
+            self.dispatch(NetworkAction::AddComment(note, comment));
+        }
+    }
+
+    /// Start editing the open note, returns its text. Only the author may
+    /// edit a note.
+    pub fn start_editing(&mut self) -> Option<String> {
+        let note = self
+            .get_open_note()
+            .filter(|note| note.author == self.display_name)?;
+        let (id, text) = (note.id.clone(), note.text.clone());
+
+        if !self.is_saved(&id) {
+            return None;
+        }
+        self.editing = Some(id);
+        Some(text)
     }
 
-    pub fn set_participants(&mut self, participants: HashSet<String>) {
+    /// Replace the text of the note being edited, its sentiment is worked out
+    /// again from the new text
+    pub fn edit_note(&mut self, text: String) {
+        let Some(id) = self.editing.take() else {
+            return;
+        };
+
+        if let Some(note) = self.notes.iter().find(|note| note.id == id) {
+            let edited = Note::new(note.author.clone(), text, self.classifier.as_ref());
+            self.dispatch(NetworkAction::EditNote(NoteEdit {
+                id,
+                text: edited.text,
+                sentiment: edited.sentiment,
+                inferred: edited.inferred,
+            }));
+        }
+    }
+
+    /// Delete the open note, only the author may delete a note
+    pub fn delete_open_note(&mut self) {
+        let Some(note) = self
+            .get_open_note()
+            .filter(|note| note.author == self.display_name)
+            .cloned()
+        else {
+            return;
+        };
+        if !self.is_saved(&note.id) {
+            return;
+        }
+
+        self.remove_note(&note.id);
+        self.close_note();
+        self.mode = Mode::Normal;
+        self.dispatch(NetworkAction::DeleteNote(note));
+    }
+
+    /// Vote for the open note, or take the vote back if already voted for
+    pub fn toggle_vote_open_note(&mut self) {
+        if let Some(id) = self.open_note.clone() {
+            self.toggle_vote(id);
+        }
+    }
+
+    /// Vote for the note, or take the vote back if already voted for
+    pub fn toggle_vote(&mut self, id: String) {
+        if self.my_votes.contains(&id) {
+            self.unvote(&vec![id]);
+        } else {
+            self.upvote(&vec![id]);
+        }
+    }
+
+    /// If the current user voted for the note
+    pub fn voted_for(&self, id: &str) -> bool {
+        self.my_votes.contains(id)
+    }
+
+    pub fn set_participants(&mut self, participants: Vec<Participant>) {
         self.participants = participants;
     }
 
+    /// Announce that the current user joined the room
+    pub fn join(&mut self) {
+        self.last_heartbeat = Instant::now();
+        self.announced_presence = self.presence();
+        self.dispatch(NetworkAction::JoinRetro(Participant::new(
+            self.session_id.clone(),
+            self.display_name.clone(),
+            self.announced_presence,
+        )));
+    }
+
+    /// What the current user is up to, as announced to the other participants
+    pub fn presence(&self) -> Presence {
+        if self.mode == Mode::Insert {
+            Presence::Typing
+        } else if self.last_input.elapsed().as_secs() as i64 > IDLE_AFTER {
+            Presence::Idle
+        } else {
+            Presence::Online
+        }
+    }
+
+    /// Register user activity, resets the idle timer
+    pub fn touch(&mut self) {
+        self.last_input = Instant::now();
+    }
+
+    fn heartbeat(&mut self) {
+        self.last_heartbeat = Instant::now();
+        self.announced_presence = self.presence();
+        self.dispatch(NetworkAction::Heartbeat(Participant::new(
+            self.session_id.clone(),
+            self.display_name.clone(),
+            self.announced_presence,
+        )));
+        self.dispatch(NetworkAction::GetParticipants);
+    }
+
+    /// Toggle the current user's reaction on the notes at the given indices
+    pub fn react(&mut self, indices: &[usize], reaction: Reaction) {
+        for index in indices {
+            if let Some(note) = self.notes.get(*index) {
+                let added = !note
+                    .reactions
+                    .get(&reaction)
+                    .is_some_and(|users| users.contains(&self.display_name));
+                self.dispatch(NetworkAction::React(ReactionChange {
+                    id: note.id.clone(),
+                    reaction,
+                    added,
+                }));
+            }
+        }
+    }
+
+    /// Index of the note reactions are added to from the picker, the open
+    /// note if there is one, otherwise the note under the cursor.
+    pub fn reaction_target(&self) -> Option<usize> {
+        match &self.open_note {
+            Some(id) => self.notes.iter().position(|note| note.id == *id),
+            None => (self.cursor < self.notes.len()).then_some(self.cursor),
+        }
+    }
+
+    /// Put the notes at the given indices in a group. Without a name they
+    /// join the group one of them is already in, or a new group named after
+    /// the first note.
+    pub fn group(&mut self, indices: &[usize], name: Option<String>) {
+        let notes: Vec<Note> = indices
+            .iter()
+            .filter_map(|index| self.notes.get(*index).cloned())
+            .collect();
+
+        let Some(first) = notes.first() else {
+            return;
+        };
+
+        let name = name
+            .or_else(|| notes.iter().find_map(|note| note.group.clone()))
+            .unwrap_or_else(|| group_name(&first.text));
+
+        let ids = notes.into_iter().map(|note| note.id).collect();
+        self.dispatch(NetworkAction::Group(GroupChange {
+            ids,
+            name: Some(name),
+        }));
+    }
+
+    /// Take the notes at the given indices out of their group
+    pub fn ungroup(&mut self, indices: &[usize]) {
+        let ids: Vec<String> = indices
+            .iter()
+            .filter_map(|index| self.notes.get(*index))
+            .filter(|note| note.group.is_some())
+            .map(|note| note.id.clone())
+            .collect();
+
+        if !ids.is_empty() {
+            self.dispatch(NetworkAction::Group(GroupChange { ids, name: None }));
+        }
+    }
+
+    /// Look for notes that could be grouped, leaving out suggestions for
+    /// notes that are already grouped together
+    pub fn suggest_groups(&mut self) {
+        self.suggestions = suggest_groups(&self.notes)
+            .into_iter()
+            .map(|indices| {
+                indices
+                    .into_iter()
+                    .map(|index| &self.notes[index])
+                    .collect::<Vec<&Note>>()
+            })
+            .filter(|notes| {
+                notes
+                    .iter()
+                    .any(|note| note.group.is_none() || note.group != notes[0].group)
+            })
+            .map(|notes| notes.iter().map(|note| note.id.clone()).collect())
+            .collect();
+    }
+
+    /// Indices of the notes in the suggestion under review, suggestions for
+    /// notes that have since been deleted are skipped
+    pub fn current_suggestion(&mut self) -> Option<Vec<usize>> {
+        while let Some(ids) = self.suggestions.first() {
+            let indices: Vec<usize> = ids
+                .iter()
+                .filter_map(|id| self.notes.iter().position(|note| note.id == *id))
+                .collect();
+
+            if indices.len() > 1 {
+                return Some(indices);
+            }
+            self.suggestions.remove(0);
+        }
+
+        None
+    }
+
+    /// Turn the notes at the given indices into action items, or back into
+    /// plain notes
+    pub fn toggle_action_items(&mut self, indices: &[usize]) {
+        for index in indices {
+            if let Some(note) = self.notes.get(*index) {
+                self.dispatch(NetworkAction::UpdateActionItem(ActionItemChange {
+                    id: note.id.clone(),
+                    action: !note.action,
+                }));
+            }
+        }
+    }
+
+    /// Mark the action items at the given indices as done, or as not done
+    pub fn toggle_done(&mut self, indices: &[usize]) {
+        for index in indices {
+            if let Some(note) = self.notes.get(*index).filter(|note| note.action) {
+                self.dispatch(NetworkAction::MarkDone(DoneChange {
+                    id: note.id.clone(),
+                    done: !note.done,
+                }));
+            }
+        }
+    }
+
+    /// Show or hide the trends, they're fetched again every time they're shown
+    pub fn toggle_trends(&mut self) {
+        self.show_trends = !self.show_trends;
+
+        if let (true, Some(series)) = (self.show_trends, self.series.clone()) {
+            self.dispatch(NetworkAction::GetTrends(series));
+        }
+    }
+
+    pub fn set_trends(&mut self, trends: Vec<RoomStats>) {
+        self.trends = trends;
+    }
+
     pub fn upvote(&mut self, ids: &Vec<String>) {
         for id in ids {
-            if let (Some(note), false) = (
-                self.notes.clone().into_iter().find(|note| note.id == *id),
-                self.my_votes.contains(id),
-            ) {
-                self.dispatch(NetworkAction::Vote(note.clone()));
-                self.my_votes.insert(note.id.clone());
+            if self.votes_left() == Some(0) {
+                self.notify(Level::Warning, "out of votes, take one back first");
+                break;
+            }
+
+            if self.my_votes.contains(id) || !self.is_saved(id) {
+                continue;
+            }
+
+            if let Some(note) = self.notes.iter_mut().find(|note| note.id == *id) {
+                // The backend adds the vote to the count it's sent
+                let mut voted = note.clone();
+                voted.voters.insert(self.display_name.clone());
+
+                note.votes = note.votes.saturating_add(1);
+                note.voters.insert(self.display_name.clone());
+                note.pending = true;
+
+                self.my_votes.insert(id.clone());
+                self.dispatch(NetworkAction::Vote(voted));
             }
         }
     }
 
     pub fn unvote(&mut self, ids: &Vec<String>) {
         for id in ids {
-            if let (Some(note), true) = (
-                &self.notes.clone().into_iter().find(|note| note.id == *id),
-                self.my_votes.contains(id),
-            ) {
-                let cloned = note.clone();
-                self.dispatch(NetworkAction::Unvote(cloned));
+            if !self.my_votes.contains(id) || !self.is_saved(id) {
+                continue;
+            }
+
+            if let Some(note) = self.notes.iter_mut().find(|note| note.id == *id) {
+                let mut unvoted = note.clone();
+                unvoted.voters.remove(&self.display_name);
+
+                note.votes = note.votes.saturating_sub(1);
+                note.voters.remove(&self.display_name);
+                note.pending = true;
+
+                self.my_votes.remove(id);
+                self.dispatch(NetworkAction::Unvote(unvoted));
+            }
+        }
+    }
+
+    /// If the backend has stored the note, notes that are still being
+    /// published can't be voted on or changed yet
+    fn is_saved(&mut self, id: &str) -> bool {
+        let saved = self
+            .notes
+            .iter()
+            .any(|note| note.id == id && note.created_at.is_some());
+
+        if !saved {
+            self.notify(
+                Level::Warning,
+                "the note isn't saved yet, try again in a bit",
+            );
+        }
+        saved
+    }
+
+    /// Use the journal left by an earlier session, the notes it publishes
+    /// are shown as pending until they're synced
+    pub fn set_journal(&mut self, journal: Journal) {
+        for action in journal.actions() {
+            if let NetworkAction::PublishNote(note) = action {
+                self.notes.push(Note {
+                    pending: true,
+                    ..note.clone()
+                });
+            }
+        }
+
+        self.journal = journal;
+        self.sort_notes(None);
+    }
+
+    /// Show a new note straight away and publish it, it stays pending until
+    /// the backend confirms it
+    pub fn publish_note(&mut self, mut note: Note) {
+        note.pending = true;
+        self.dispatch(NetworkAction::PublishNote(note.clone()));
+
+        let under_cursor = self.note_under_cursor();
+        self.notes.push(note);
+        self.sort_notes(under_cursor);
+    }
+
+    /// Replace a pending note with the one the backend stored. A published
+    /// note is found by its client side id, or by the backend's id if the
+    /// notes were loaded since.
+    pub fn confirm_note(&mut self, id: &str, confirmed: Note) {
+        let under_cursor = self.note_under_cursor().map(|cursor| {
+            if document_id(&cursor) == document_id(id) {
+                confirmed.id.clone()
+            } else {
+                cursor
+            }
+        });
+
+        match self
+            .notes
+            .iter_mut()
+            .find(|note| note.document_id() == document_id(id))
+        {
+            Some(note) => *note = confirmed,
+            None => self.notes.push(confirmed),
+        }
+        self.sort_notes(under_cursor);
+    }
 
-                // @TODO remove the current
+    /// Undo the local changes of an action the backend rejected
+    pub fn roll_back(&mut self, action: &NetworkAction) {
+        let me = self.display_name.clone();
+
+        match action {
+            NetworkAction::PublishNote(note) => self.remove_note(&note.id),
+            NetworkAction::Vote(rejected) => {
+                if let Some(note) = self.notes.iter_mut().find(|note| note.id == rejected.id) {
+                    note.votes = note.votes.saturating_sub(1);
+                    note.voters.remove(&me);
+                    note.pending = false;
+                }
+                self.my_votes.remove(&rejected.id);
+            }
+            NetworkAction::Unvote(rejected) => {
+                if let Some(note) = self.notes.iter_mut().find(|note| note.id == rejected.id) {
+                    note.votes = note.votes.saturating_add(1);
+                    note.voters.insert(me);
+                    note.pending = false;
+                }
+                self.my_votes.insert(rejected.id.clone());
             }
+            _ => {}
+        }
+    }
+
+    /// Send a heartbeat when it's due, returns if the screen needs to be
+    /// drawn again
+    pub fn tick(&mut self) -> bool {
+        if self.presence() != self.announced_presence
+            || self.last_heartbeat.elapsed() >= HEARTBEAT_INTERVAL
+        {
+            self.heartbeat();
+        }
+
+        let clock_view = self.clock_view();
+        let changed = clock_view != self.clock_view;
+        self.clock_view = clock_view;
+
+        std::mem::take(&mut self.redraw) || changed
+    }
+
+    /// Draw the screen again on the next tick
+    pub fn request_redraw(&mut self) {
+        self.redraw = true;
+    }
+
+    fn clock_view(&self) -> ClockView {
+        let now = Utc::now().timestamp();
+
+        ClockView {
+            toasts: self.toasts().len(),
+            minute: if self.relative_time { now / 60 } else { 0 },
+            presences: self
+                .participants
+                .iter()
+                .map(|participant| participant.effective_presence(now))
+                .collect(),
         }
     }
 
-    pub fn tick(&mut self) {
-        self.tick_count += 1;
+    /// Drop a note from the list, without waiting for the backend
+    pub fn remove_note(&mut self, id: &str) {
+        self.notes.retain(|note| note.id != id);
+        self.cursor = self.cursor.min(self.notes.len().saturating_sub(1));
+    }
+
+    /// Select the notes at the given indices for the command
+    pub fn select_rows(&mut self, rows: &[usize]) {
+        self.selected = rows
+            .iter()
+            .filter_map(|row| self.notes.get(*row).map(|note| note.id.clone()))
+            .collect();
     }
 
-    pub fn remove_note(&mut self, id: &String) {
-        println!("{}", id);
+    /// Indices of the selected notes where they are now, in the order they
+    /// were selected. Notes deleted since are left out.
+    pub fn selected_rows(&self) -> Vec<usize> {
+        self.selected
+            .iter()
+            .filter_map(|id| self.notes.iter().position(|note| note.id == *id))
+            .collect()
     }
 
-    pub fn select_rows(&mut self, rows: &Vec<usize>) {
-        self.selected_rows = rows.to_owned();
+    pub fn is_selected(&self, id: &str) -> bool {
+        self.selected.iter().any(|selected| selected == id)
     }
 
     pub fn deselect_rows(&mut self) {
-        self.selected_rows = vec![];
+        self.selected = vec![];
     }
 
     pub fn sentiment_count(&self) -> [(Sentiment, usize); 3] {
@@ -129,4 +895,147 @@ impl State {
             (Sentiment::Neutral, total.2),
         ]
     }
+
+    /// The note with the most votes for the given sentiment
+    pub fn top_note(&self, sentiment: Sentiment) -> Option<&Note> {
+        self.notes
+            .iter()
+            .filter(|note| note.sentiment == sentiment && note.votes > 0)
+            .max_by_key(|note| note.votes)
+    }
+
+    /// How many votes every participant has cast, most first
+    pub fn votes_per_participant(&self) -> Vec<(String, u64)> {
+        count_most_first(self.notes.iter().flat_map(|note| note.voters.iter()))
+    }
+
+    /// How many notes every author has written, most first
+    pub fn notes_per_author(&self) -> Vec<(String, u64)> {
+        count_most_first(self.notes.iter().map(|note| &note.author))
+    }
+}
+
+fn count_most_first<'a>(names: impl Iterator<Item = &'a String>) -> Vec<(String, u64)> {
+    let mut counts: Vec<(String, u64)> = vec![];
+
+    for name in names {
+        match counts.iter_mut().find(|(counted, _)| counted == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name.clone(), 1)),
+        }
+    }
+
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Name for a new group, the start of the text of its first note
+fn group_name(text: &str) -> String {
+    const MAX_LENGTH: usize = 20;
+
+    let text = text.trim();
+    if text.chars().count() <= MAX_LENGTH {
+        return text.to_string();
+    }
+
+    let mut name: String = text.chars().take(MAX_LENGTH - 1).collect();
+    name.push('…');
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+
+    fn state() -> State {
+        let (sender, _receiver) = unbounded_channel();
+        State::new(sender, "ann".to_string())
+    }
+
+    /// The note as the backend stored it
+    fn stored(note: &Note) -> Note {
+        Note {
+            id: format!(
+                "projects/p/databases/(default)/documents/retros/r/notes/{}",
+                note.id
+            ),
+            created_at: Some(1),
+            pending: false,
+            ..note.clone()
+        }
+    }
+
+    #[test]
+    fn published_notes_are_merged_with_their_stored_copy() {
+        let mut state = state();
+        let note = Note::new("ann".to_string(), "hi".to_string(), &Lexicon::default());
+        state.publish_note(note.clone());
+
+        state.set_notes(vec![stored(&note)]);
+        assert_eq!(state.notes.len(), 1);
+        assert_eq!(state.notes[0].id, stored(&note).id);
+        assert!(!state.notes[0].pending);
+
+        // The publish is confirmed after the notes were loaded
+        state.confirm_note(&note.id, stored(&note));
+        assert_eq!(state.notes.len(), 1);
+    }
+
+    #[test]
+    fn journaled_notes_that_got_through_are_shown_once() {
+        let note = Note::new("ann".to_string(), "hi".to_string(), &Lexicon::default());
+
+        // After a restart the replay finds the note already stored, before or
+        // after the notes are loaded
+        for replay_first in [true, false] {
+            let mut journal = Journal::default();
+            journal
+                .push(NetworkAction::PublishNote(note.clone()))
+                .unwrap();
+            let mut state = state();
+            state.set_journal(journal);
+
+            if replay_first {
+                state.confirm_note(&note.id, stored(&note));
+            }
+            state.set_notes(vec![stored(&note)]);
+            if !replay_first {
+                state.confirm_note(&note.id, stored(&note));
+            }
+
+            assert_eq!(state.notes.len(), 1);
+            assert_eq!(state.notes[0].id, stored(&note).id);
+            assert!(!state.notes[0].pending);
+        }
+    }
+
+    #[test]
+    fn pending_changes_win_over_the_stored_copy() {
+        let mut state = state();
+        let note = stored(&Note::new(
+            "ann".to_string(),
+            "hi".to_string(),
+            &Lexicon::default(),
+        ));
+        state.set_notes(vec![note.clone()]);
+        state.notes[0].votes = 1;
+        state.notes[0].pending = true;
+
+        state.set_notes(vec![note]);
+        assert_eq!(state.notes.len(), 1);
+        assert_eq!(state.notes[0].votes, 1);
+    }
+
+    #[test]
+    fn pending_notes_the_backend_doesnt_have_are_kept() {
+        let mut state = state();
+        let note = Note::new("ann".to_string(), "hi".to_string(), &Lexicon::default());
+        state.publish_note(note.clone());
+
+        state.set_notes(vec![]);
+        assert_eq!(state.notes.len(), 1);
+        assert_eq!(state.notes[0].id, note.id);
+    }
 }
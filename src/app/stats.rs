@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+
+use super::{note::Note, room::Room, sentiment::Sentiment};
+
+#[derive(Clone, Debug)]
+/// How a single retro went, used to follow a series of retros over time
+pub struct RoomStats {
+    /// Id of the room
+    pub room_id: String,
+
+    /// Human readable name of the room
+    pub name: String,
+
+    /// Unix timestamp (seconds) of when the room was created
+    pub created_at: i64,
+
+    /// How many notes were written
+    pub notes: usize,
+
+    /// How many notes were positive
+    pub happy: usize,
+
+    /// How many notes were negative
+    pub sad: usize,
+
+    /// How many people wrote, voted or reacted
+    pub participants: usize,
+
+    /// How many notes were turned into action items
+    pub action_items: usize,
+
+    /// How many of the action items were done
+    pub done: usize,
+}
+
+impl RoomStats {
+    /// Header of the CSV export, in the same order as [`RoomStats::to_csv`]
+    pub const CSV_HEADER: &'static str =
+        "room_id,name,created_at,notes,happy,sad,participants,action_items,done,sentiment_ratio,completion_rate";
+
+    pub fn new(room: &Room, notes: &[Note]) -> Self {
+        let count = |sentiment: Sentiment| {
+            notes
+                .iter()
+                .filter(|note| note.sentiment == sentiment)
+                .count()
+        };
+
+        let participants: BTreeSet<&String> = notes
+            .iter()
+            .flat_map(|note| {
+                std::iter::once(&note.author)
+                    .chain(note.voters.iter())
+                    .chain(note.reactions.values().flatten())
+            })
+            .collect();
+
+        RoomStats {
+            room_id: room.id.clone(),
+            name: room.name.clone(),
+            created_at: room.created_at,
+            notes: notes.len(),
+            happy: count(Sentiment::Happy),
+            sad: count(Sentiment::Sad),
+            participants: participants.len(),
+            action_items: notes.iter().filter(|note| note.action).count(),
+            done: notes.iter().filter(|note| note.action && note.done).count(),
+        }
+    }
+
+    /// Share of the positive notes among the notes that were either positive
+    /// or negative, None if there weren't any
+    pub fn sentiment_ratio(&self) -> Option<f64> {
+        ratio(self.happy, self.happy + self.sad)
+    }
+
+    /// Share of the action items that were done, None if there weren't any
+    pub fn completion_rate(&self) -> Option<f64> {
+        ratio(self.done, self.action_items)
+    }
+
+    /// The stats as a line of CSV
+    pub fn to_csv(&self) -> String {
+        let optional = |value: Option<f64>| value.map(|value| format!("{value:.2}"));
+
+        [
+            csv_field(&self.room_id),
+            csv_field(&self.name),
+            self.created_at.to_string(),
+            self.notes.to_string(),
+            self.happy.to_string(),
+            self.sad.to_string(),
+            self.participants.to_string(),
+            self.action_items.to_string(),
+            self.done.to_string(),
+            optional(self.sentiment_ratio()).unwrap_or_default(),
+            optional(self.completion_rate()).unwrap_or_default(),
+        ]
+        .join(",")
+    }
+}
+
+fn ratio(part: usize, total: usize) -> Option<f64> {
+    (total > 0).then(|| part as f64 / total as f64)
+}
+
+/// Quote a CSV field if it needs to be
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,115 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Wrap text into lines of at most `width` columns, breaking between words
+/// where possible. Wide characters, such as CJK and emoji, take up two
+/// columns.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![];
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for word in paragraph.split_whitespace() {
+            if line_width > 0 && line_width + 1 + word.width() > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+
+            // Only words wider than a whole line are broken up
+            for c in word.chars() {
+                let char_width = c.width().unwrap_or(0);
+                if line_width > 0 && line_width + char_width > width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+                line.push(c);
+                line_width += char_width;
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Cut text down to at most `width` columns, ending in `…` if anything was
+/// cut off
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if truncated_width + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        truncated_width += char_width;
+    }
+
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_between_words() {
+        assert_eq!(wrap("one two three", 7), vec!["one two", "three"]);
+        assert_eq!(wrap("one\n\ntwo", 10), vec!["one", "", "two"]);
+    }
+
+    #[test]
+    fn wrap_counts_wide_characters_twice() {
+        assert_eq!(wrap("你好世界", 4), vec!["你好", "世界"]);
+        assert_eq!(wrap("🎉🎉🎉", 5), vec!["🎉🎉", "🎉"]);
+        assert_eq!(wrap("a 你好", 3), vec!["a", "你", "好"]);
+    }
+
+    #[test]
+    fn wrap_breaks_up_words_longer_than_a_line() {
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("hi abcdefgh", 4), vec!["hi", "abcd", "efgh"]);
+    }
+
+    #[test]
+    fn wrap_puts_a_wide_character_on_its_own_line_when_it_doesnt_fit() {
+        assert_eq!(wrap("你好", 1), vec!["你", "好"]);
+        assert_eq!(wrap("ab", 0), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn truncate_keeps_text_that_fits() {
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("a", 1), "a");
+        assert_eq!(truncate("", 0), "");
+    }
+
+    #[test]
+    fn truncate_ends_in_an_ellipsis() {
+        assert_eq!(truncate("hello", 4), "hel…");
+        assert_eq!(truncate("你好世界", 5), "你好…");
+        assert_eq!(truncate("你好", 3), "你…");
+    }
+
+    #[test]
+    fn truncate_to_zero_and_one_columns() {
+        assert_eq!(truncate("hello", 0), "");
+        assert_eq!(truncate("hello", 1), "…");
+        assert_eq!(truncate("你", 1), "…");
+    }
+}
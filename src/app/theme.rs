@@ -0,0 +1,376 @@
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use tui::style::{Color, Modifier, Style};
+
+use crate::config::ThemeConfig;
+
+use super::mode::Mode;
+
+#[derive(Clone, Debug)]
+/// The colors used by every widget
+pub struct Theme {
+    /// Status bar color in normal mode
+    pub normal_mode: Color,
+
+    /// Status bar color in insert mode
+    pub insert_mode: Color,
+
+    /// Status bar color in command mode
+    pub command_mode: Color,
+
+    /// Status bar color while looking at a note
+    pub detail_mode: Color,
+
+    /// Text color of the status bar
+    pub status_fg: Color,
+
+    /// Background of notes selected in command mode
+    pub selection: Color,
+
+    /// Background of the note under the cursor in normal mode
+    pub cursor: Color,
+
+    /// Background of the new note editor
+    pub editor_bg: Color,
+
+    /// Text color of the new note editor
+    pub editor_fg: Color,
+
+    /// Text color of the command box
+    pub command: Color,
+
+    /// Background of the command box
+    pub command_bg: Color,
+
+    /// Background of the help box
+    pub help_bg: Color,
+
+    /// Text color of the help box
+    pub help_fg: Color,
+
+    /// Participants that are online
+    pub online: Color,
+
+    /// Participants that are idle
+    pub idle: Color,
+
+    /// Participants that are writing a note
+    pub typing: Color,
+
+    /// Bars of the dashboard charts
+    pub chart: Color,
+
+    /// Things that need attention, such as a note that is too long
+    pub warning: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    /// Light text on a dark terminal
+    pub fn dark() -> Self {
+        Theme {
+            normal_mode: Color::White,
+            insert_mode: Color::Blue,
+            command_mode: Color::Red,
+            detail_mode: Color::Magenta,
+            status_fg: Color::Reset,
+            selection: Color::LightGreen,
+            cursor: Color::DarkGray,
+            editor_bg: Color::DarkGray,
+            editor_fg: Color::White,
+            command: Color::LightRed,
+            command_bg: Color::Reset,
+            help_bg: Color::White,
+            help_fg: Color::Black,
+            online: Color::LightGreen,
+            idle: Color::DarkGray,
+            typing: Color::LightBlue,
+            chart: Color::LightBlue,
+            warning: Color::LightRed,
+        }
+    }
+
+    /// Dark text on a light terminal
+    pub fn light() -> Self {
+        Theme {
+            normal_mode: Color::Gray,
+            insert_mode: Color::LightBlue,
+            command_mode: Color::LightRed,
+            detail_mode: Color::LightMagenta,
+            status_fg: Color::Reset,
+            selection: Color::LightYellow,
+            cursor: Color::Gray,
+            editor_bg: Color::Gray,
+            editor_fg: Color::Black,
+            command: Color::Red,
+            command_bg: Color::Reset,
+            help_bg: Color::Black,
+            help_fg: Color::White,
+            online: Color::Green,
+            idle: Color::Gray,
+            typing: Color::Blue,
+            chart: Color::Blue,
+            warning: Color::Red,
+        }
+    }
+
+    /// Bright, distinct colors on black
+    pub fn high_contrast() -> Self {
+        Theme {
+            normal_mode: Color::White,
+            insert_mode: Color::LightCyan,
+            command_mode: Color::LightYellow,
+            detail_mode: Color::LightMagenta,
+            status_fg: Color::Reset,
+            selection: Color::Yellow,
+            cursor: Color::White,
+            editor_bg: Color::Black,
+            editor_fg: Color::White,
+            command: Color::LightYellow,
+            command_bg: Color::Reset,
+            help_bg: Color::Black,
+            help_fg: Color::White,
+            online: Color::LightGreen,
+            idle: Color::White,
+            typing: Color::LightCyan,
+            chart: Color::LightCyan,
+            warning: Color::LightYellow,
+        }
+    }
+
+    /// No colors at all, for terminals with `NO_COLOR` set. Selections are
+    /// shown reversed instead.
+    pub fn no_color() -> Self {
+        Theme {
+            normal_mode: Color::Reset,
+            insert_mode: Color::Reset,
+            command_mode: Color::Reset,
+            detail_mode: Color::Reset,
+            status_fg: Color::Reset,
+            selection: Color::Reset,
+            cursor: Color::Reset,
+            editor_bg: Color::Reset,
+            editor_fg: Color::Reset,
+            command: Color::Reset,
+            command_bg: Color::Reset,
+            help_bg: Color::Reset,
+            help_fg: Color::Reset,
+            online: Color::Reset,
+            idle: Color::Reset,
+            typing: Color::Reset,
+            chart: Color::Reset,
+            warning: Color::Reset,
+        }
+    }
+
+    /// Look up a theme by name, user defined themes take precedence over the
+    /// built in ones. `NO_COLOR` overrides everything.
+    pub fn load(name: Option<&str>, themes: &HashMap<String, ThemeConfig>) -> Result<Self> {
+        if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Ok(Theme::no_color());
+        }
+
+        Self::named(name.unwrap_or("dark"), themes, 0)
+    }
+
+    fn named(name: &str, themes: &HashMap<String, ThemeConfig>, depth: usize) -> Result<Self> {
+        if depth > themes.len() {
+            bail!("theme `{name}` inherits from itself");
+        }
+
+        if let Some(config) = themes.get(name) {
+            let base = Self::named(config.base.as_deref().unwrap_or("dark"), themes, depth + 1)?;
+            return base.with_overrides(config);
+        }
+
+        match name {
+            "dark" => Ok(Theme::dark()),
+            "light" => Ok(Theme::light()),
+            "high-contrast" => Ok(Theme::high_contrast()),
+            "none" => Ok(Theme::no_color()),
+            _ => bail!("unknown theme `{name}`"),
+        }
+    }
+
+    fn with_overrides(mut self, config: &ThemeConfig) -> Result<Self> {
+        let overrides = [
+            (&config.normal_mode, &mut self.normal_mode),
+            (&config.insert_mode, &mut self.insert_mode),
+            (&config.command_mode, &mut self.command_mode),
+            (&config.detail_mode, &mut self.detail_mode),
+            (&config.status_fg, &mut self.status_fg),
+            (&config.selection, &mut self.selection),
+            (&config.cursor, &mut self.cursor),
+            (&config.editor_bg, &mut self.editor_bg),
+            (&config.editor_fg, &mut self.editor_fg),
+            (&config.command, &mut self.command),
+            (&config.command_bg, &mut self.command_bg),
+            (&config.help_bg, &mut self.help_bg),
+            (&config.help_fg, &mut self.help_fg),
+            (&config.online, &mut self.online),
+            (&config.idle, &mut self.idle),
+            (&config.typing, &mut self.typing),
+            (&config.chart, &mut self.chart),
+            (&config.warning, &mut self.warning),
+        ];
+
+        for (value, color) in overrides {
+            if let Some(value) = value {
+                *color = parse_color(value)?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Status bar color for the given mode
+    pub fn mode_color(&self, mode: &Mode) -> Color {
+        match mode {
+            Mode::Normal => self.normal_mode,
+            Mode::Insert => self.insert_mode,
+            Mode::Command => self.command_mode,
+            Mode::Detail => self.detail_mode,
+        }
+    }
+
+    /// Style of a note selected in command mode
+    pub fn selected(&self) -> Style {
+        match self.selection {
+            Color::Reset => Style::default().add_modifier(Modifier::REVERSED),
+            color => Style::default().bg(color),
+        }
+    }
+
+    /// Style of the note under the cursor
+    pub fn cursor(&self) -> Style {
+        match self.cursor {
+            Color::Reset => Style::default().add_modifier(Modifier::REVERSED),
+            color => Style::default().bg(color),
+        }
+    }
+
+    /// Style of the help box
+    pub fn help(&self) -> Style {
+        match (self.help_bg, self.help_fg) {
+            (Color::Reset, Color::Reset) => Style::default().add_modifier(Modifier::REVERSED),
+            (bg, fg) => Style::default().bg(bg).fg(fg),
+        }
+    }
+}
+
+/// Parse a color name (`lightgreen`), a 256 color index (`42`) or a hex
+/// color (`#ff8800`).
+fn parse_color(value: &str) -> Result<Color> {
+    let color = match value.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        // Numbers are parsed as given, `-1` isn't color 1
+        _ if value.starts_with('#') && value.len() == 7 && value.is_ascii() => {
+            let channel = |i: usize| u8::from_str_radix(&value[i..i + 2], 16);
+            match (channel(1), channel(3), channel(5)) {
+                (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                _ => bail!("invalid color `{value}`"),
+            }
+        }
+        _ => Color::Indexed(u8::from_str(value).map_err(|_| anyhow!("invalid color `{value}`"))?),
+    };
+
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_parse() {
+        for (value, color) in [
+            ("lightgreen", Color::LightGreen),
+            ("Light-Green", Color::LightGreen),
+            ("dark_grey", Color::DarkGray),
+            ("default", Color::Reset),
+            ("42", Color::Indexed(42)),
+            ("#ff8800", Color::Rgb(255, 136, 0)),
+            ("#FF8800", Color::Rgb(255, 136, 0)),
+        ] {
+            assert_eq!(parse_color(value).unwrap(), color, "{value}");
+        }
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        for value in [
+            "", "purple", "256", "-1", "#ff88", "#ff88001", "#gg8800", "#ff880é",
+        ] {
+            assert!(parse_color(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn user_themes_override_their_base() {
+        let themes = HashMap::from([(
+            "mine".to_string(),
+            ThemeConfig {
+                base: Some("light".to_string()),
+                command_bg: Some("blue".to_string()),
+                ..Default::default()
+            },
+        )]);
+
+        let theme = Theme::named("mine", &themes, 0).unwrap();
+        assert_eq!(theme.command_bg, Color::Blue);
+        assert_eq!(theme.command, Theme::light().command);
+
+        let broken = HashMap::from([(
+            "mine".to_string(),
+            ThemeConfig {
+                chart: Some("purple".to_string()),
+                ..Default::default()
+            },
+        )]);
+        assert!(Theme::named("mine", &broken, 0).is_err());
+        assert!(Theme::named("neon", &themes, 0).is_err());
+    }
+
+    #[test]
+    fn no_color_wins_over_the_chosen_theme() {
+        // The only test that touches NO_COLOR, so it can't race another one
+        std::env::remove_var("NO_COLOR");
+        let theme = Theme::load(Some("light"), &HashMap::new()).unwrap();
+        assert_eq!(theme.command, Theme::light().command);
+
+        std::env::set_var("NO_COLOR", "");
+        let theme = Theme::load(Some("light"), &HashMap::new()).unwrap();
+        assert_eq!(theme.command, Theme::light().command);
+
+        std::env::set_var("NO_COLOR", "1");
+        let theme = Theme::load(Some("light"), &HashMap::new()).unwrap();
+        assert_eq!(theme.command, Color::Reset);
+        assert_eq!(
+            theme.selected(),
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+        std::env::remove_var("NO_COLOR");
+    }
+}
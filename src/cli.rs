@@ -1,21 +1,210 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+
+use crate::{
+    app::room::Template,
+    config::{Config, CONFIG_ENV},
+};
 
 #[derive(Parser, Debug, Default, Clone)]
 /// Retro is a CLI tool to host and and run retros
 #[command(author, version, about, long_about = None)]
 pub struct RetroArgs {
+    #[command(subcommand)]
+    pub command: Option<RoomCommand>,
+
     /// room id to connect  to
-    #[arg(short, long)]
-    pub room: String,
+    #[arg(short, long, env = "RETRO_ROOM")]
+    pub room: Option<String>,
 
     /// Your display name during the retro
-    #[arg(short, long)]
-    pub display_name: String,
+    #[arg(short, long, global = true, env = "RETRO_DISPLAY_NAME")]
+    pub display_name: Option<String>,
+
+    /// Config file to use instead of `$XDG_CONFIG_HOME/retro/config.toml`
+    #[arg(long, global = true, env = CONFIG_ENV)]
+    pub config_file: Option<PathBuf>,
+
+    /// Firestore project the retros are stored in
+    #[arg(long, global = true, env = "RETRO_PROJECT_ID")]
+    pub project_id: Option<String>,
+
+    /// Firestore endpoint to connect to
+    #[arg(long, global = true, env = "RETRO_ENDPOINT")]
+    pub endpoint: Option<String>,
+
+    /// Settings read from the config file
+    #[arg(skip)]
+    pub config: Config,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+/// Manage retro rooms
+pub enum RoomCommand {
+    /// Create a new room and print its shareable id
+    Create {
+        /// Human readable name of the room
+        #[arg(short, long, default_value = "retro")]
+        name: String,
+
+        /// What kind of retro to run
+        #[arg(short, long, value_enum, default_value_t = Template::Default)]
+        template: Template,
+
+        /// How many notes each participant may vote for, unlimited if left out
+        #[arg(short, long)]
+        votes: Option<u8>,
+
+        /// Password needed to join the room
+        #[arg(short, long)]
+        password: Option<String>,
+
+        /// Series the room belongs to, such as the team's sprint retros
+        #[arg(short, long)]
+        series: Option<String>,
+
+        /// Hide who voted for which note
+        #[arg(short, long)]
+        anonymous: bool,
+    },
+
+    /// Join a room
+    Join {
+        /// Id of the room, as printed by `retro create`
+        room: String,
+
+        /// Password of the room, if it has one
+        #[arg(short, long)]
+        password: Option<String>,
+    },
+
+    /// List recently created rooms
+    List {
+        /// Include archived rooms
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Archive a room, it's kept but can no longer be joined
+    Archive {
+        /// Id of the room
+        room: String,
+
+        /// Password of the room, if it has one
+        #[arg(short, long)]
+        password: Option<String>,
+    },
+
+    /// Delete a room and all of its notes
+    Delete {
+        /// Id of the room
+        room: String,
+
+        /// Password of the room, if it has one
+        #[arg(short, long)]
+        password: Option<String>,
+
+        /// Delete without asking first
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show how the retros in a series went over time
+    Stats {
+        /// Name of the series, as given to `retro create --series`
+        series: String,
+
+        /// Print CSV instead of a chart
+        #[arg(long)]
+        csv: bool,
+    },
 }
 
 impl RetroArgs {
-    /// Parse the CLI parameters and return a new struct
+    /// Parse the CLI parameters and return a new struct. Flags take
+    /// precedence over environment variables, which take precedence over
+    /// the config file.
     pub fn new() -> RetroArgs {
-        RetroArgs::parse()
+        let mut args = RetroArgs::parse();
+
+        let path = args.config_file.clone().or_else(Config::default_path);
+        if let Some(path) = path {
+            args.config = Config::load(&path).unwrap_or_else(|e| {
+                RetroArgs::command()
+                    .error(ErrorKind::Io, format!("{e:#}"))
+                    .exit()
+            });
+        }
+
+        args.merge_config();
+        args
+    }
+
+    fn merge_config(&mut self) {
+        let config = &self.config;
+
+        self.display_name = self.display_name.take().or(config.display_name.clone());
+        self.project_id = self.project_id.take().or(config.backend.project_id.clone());
+        self.endpoint = self.endpoint.take().or(config.backend.endpoint.clone());
+    }
+
+    /// The room to connect to, either given through `retro join` or `--room`
+    pub fn room(&self) -> Option<&String> {
+        match &self.command {
+            Some(RoomCommand::Join { room, .. }) => Some(room),
+            _ => self.room.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn flags_beat_env_beats_config_file_beats_defaults() {
+        let path = std::env::temp_dir().join(format!("retro-config-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            r#"
+                display_name = "file"
+
+                [backend]
+                project_id = "file-project"
+                endpoint = "http://file"
+            "#,
+        )
+        .unwrap();
+
+        // The only test that sets these variables, so it can't race another one
+        std::env::set_var("RETRO_DISPLAY_NAME", "env");
+        std::env::set_var("RETRO_PROJECT_ID", "env-project");
+        std::env::remove_var("RETRO_ENDPOINT");
+        std::env::remove_var("RETRO_ROOM");
+
+        let mut args = RetroArgs::try_parse_from(["retro", "--display-name", "flag"]).unwrap();
+        args.config = Config::load(&path).unwrap();
+        args.merge_config();
+
+        assert_eq!(args.display_name.as_deref(), Some("flag"));
+        assert_eq!(args.project_id.as_deref(), Some("env-project"));
+        assert_eq!(args.endpoint.as_deref(), Some("http://file"));
+
+        std::env::remove_var("RETRO_DISPLAY_NAME");
+        std::env::remove_var("RETRO_PROJECT_ID");
+        fs::remove_file(&path).unwrap();
+
+        // Without a config file the built in defaults are left
+        let mut args = RetroArgs::try_parse_from(["retro"]).unwrap();
+        args.config = Config::load(&path).unwrap();
+        args.merge_config();
+
+        assert_eq!(args.display_name, None);
+        assert_eq!(args.project_id, None);
+        assert!(!args.config.compact);
+        assert!(args.config.keybindings.is_empty());
     }
 }
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Environment variable that overrides where the config file is read from
+pub const CONFIG_ENV: &str = "RETRO_CONFIG";
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// User settings, read from `$XDG_CONFIG_HOME/retro/config.toml`.
+///
+/// Every setting can be overridden, from highest to lowest precedence:
+///
+/// 1. CLI flags, e.g. `--display-name`
+/// 2. Environment variables, e.g. `RETRO_DISPLAY_NAME`
+/// 3. This config file
+/// 4. Built in defaults
+pub struct Config {
+    /// Display name used when `--display-name` isn't given
+    pub display_name: Option<String>,
+
+    /// Where the retros are stored
+    pub backend: BackendConfig,
+
+    /// Name of the color scheme to use, either built in (`dark`, `light`,
+    /// `high-contrast`) or one of `themes`
+    pub theme: Option<String>,
+
+    /// User defined color schemes
+    pub themes: HashMap<String, ThemeConfig>,
+
+    /// Overrides for the default keybindings, action name to key
+    pub keybindings: HashMap<String, String>,
+
+    /// Cut long notes off at the end of the line instead of wrapping them
+    pub compact: bool,
+
+    /// Show how long ago each note was written in the notes list
+    pub relative_time: bool,
+
+    /// Lexicon file with extra `word score` lines for guessing the sentiment
+    /// of notes, scores range from -3 to 3
+    pub lexicon: Option<PathBuf>,
+
+    /// Directory exports are written to, defaults to the working directory
+    pub export_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Connection settings for the backend
+pub struct BackendConfig {
+    /// Firestore project id
+    pub project_id: Option<String>,
+
+    /// Firestore endpoint, useful for pointing at a local emulator
+    pub endpoint: Option<String>,
+}
+
+impl Config {
+    /// Default location of the config file, respects `$XDG_CONFIG_HOME` and
+    /// falls back to the platform config dir.
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(dirs::config_dir)
+            .map(|dir| dir.join("retro").join("config.toml"))
+    }
+
+    /// Read the config from the given path, a missing file yields the
+    /// default config.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+        };
+
+        toml::from_str(&contents).with_context(|| format!("invalid config {}", path.display()))
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// A user defined color scheme. Colors are names (`lightgreen`), 256 color
+/// indices (`42`) or hex (`#ff8800`), anything left out is taken from `base`.
+pub struct ThemeConfig {
+    /// Theme to start from, defaults to `dark`
+    pub base: Option<String>,
+
+    pub normal_mode: Option<String>,
+    pub insert_mode: Option<String>,
+    pub command_mode: Option<String>,
+    pub detail_mode: Option<String>,
+    pub status_fg: Option<String>,
+    pub selection: Option<String>,
+    pub cursor: Option<String>,
+    pub editor_bg: Option<String>,
+    pub editor_fg: Option<String>,
+    pub command: Option<String>,
+    pub command_bg: Option<String>,
+    pub help_bg: Option<String>,
+    pub help_fg: Option<String>,
+    pub online: Option<String>,
+    pub idle: Option<String>,
+    pub typing: Option<String>,
+    pub chart: Option<String>,
+    pub warning: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        assert!(toml::from_str::<Config>("display_nam = \"me\"").is_err());
+        assert!(toml::from_str::<Config>("[backend]\nproject = \"p\"").is_err());
+    }
+
+    #[test]
+    fn missing_settings_are_left_to_the_defaults() {
+        let config: Config = toml::from_str("compact = true\n[keybindings]\nquit = \"x\"").unwrap();
+
+        assert!(config.compact);
+        assert!(!config.relative_time);
+        assert_eq!(config.display_name, None);
+        assert_eq!(config.backend.project_id, None);
+        assert_eq!(
+            config.keybindings.get("quit").map(String::as_str),
+            Some("x")
+        );
+    }
+}
@@ -1,48 +1,65 @@
-use std::{
-    sync::mpsc::{Receiver, Sender},
-    thread,
-    time::Duration,
-};
+use std::time::Duration;
 
-use anyhow::Result;
-use crossterm::event::{self, KeyEvent};
+use anyhow::{bail, Result};
+use crossterm::event::{self, EventStream, KeyEvent, MouseEvent, MouseEventKind};
+use futures::StreamExt;
+use tokio::time::{interval, Interval, MissedTickBehavior};
 
 #[derive(Debug)]
+/// Terminal input, keys and mouse, with a tick in between for the things that happen on
+/// their own, such as heartbeats
 pub struct Events {
-    pub tick_rate: Duration,
-    rx: Receiver<Event<KeyEvent>>,
-    _tx: Sender<Event<KeyEvent>>,
+    stream: EventStream,
+    ticks: Interval,
 }
 
 #[derive(Debug)]
 pub enum Event<I> {
     Input(I),
+
+    /// A click or a turn of the wheel
+    Mouse(MouseEvent),
+
+    /// Text pasted into the terminal, all at once rather than key by key
+    Paste(String),
+
+    /// The terminal changed size, with the new width and height
+    Resize(u16, u16),
+
     Tick,
 }
 
 impl Events {
     pub fn new(tick_rate: Duration) -> Self {
-        let (tx, rx) = std::sync::mpsc::channel();
-
-        let event_tx = tx.clone();
-
-        thread::spawn(move || loop {
-            if event::poll(tick_rate).unwrap() {
-                if let event::Event::Key(key) = event::read().unwrap() {
-                    event_tx.send(Event::Input(key)).unwrap();
-                }
-            }
-            event_tx.send(Event::Tick).unwrap();
-        });
+        let mut ticks = interval(tick_rate);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         Events {
-            tick_rate,
-            _tx: tx,
-            rx,
+            stream: EventStream::new(),
+            ticks,
         }
     }
 
-    pub fn next(&self) -> Result<Event<KeyEvent>, std::sync::mpsc::RecvError> {
-        self.rx.recv()
+    /// Wait for the next key press, mouse event, paste, resize or tick
+    pub async fn next(&mut self) -> Result<Event<KeyEvent>> {
+        loop {
+            tokio::select! {
+                event = self.stream.next() => match event {
+                    Some(Ok(event::Event::Key(key))) => return Ok(Event::Input(key)),
+                    // Moving the mouse around doesn't do anything
+                    Some(Ok(event::Event::Mouse(mouse))) if mouse.kind != MouseEventKind::Moved => {
+                        return Ok(Event::Mouse(mouse))
+                    }
+                    Some(Ok(event::Event::Paste(text))) => return Ok(Event::Paste(text)),
+                    Some(Ok(event::Event::Resize(width, height))) => {
+                        return Ok(Event::Resize(width, height))
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                    None => bail!("the terminal stopped sending input"),
+                },
+                _ = self.ticks.tick() => return Ok(Event::Tick),
+            }
+        }
     }
 }
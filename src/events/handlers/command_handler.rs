@@ -1,87 +1,126 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::KeyEvent;
 use tui_textarea::{CursorMove, TextArea};
 
-use crate::app::{mode::Mode, state::State};
+use crate::app::{
+    command::Command, keymap::Action, mode::Mode, notification::Level, reaction::Reaction,
+    sort::Sort, state::State,
+};
 
-pub fn handle_command(input: KeyEvent, state: &mut State, textarea: &mut TextArea<'_>) {
+pub fn handle_command(
+    action: Option<Action>,
+    input: KeyEvent,
+    state: &mut State,
+    textarea: &mut TextArea<'_>,
+) {
     if state.mode != Mode::Command {
         return;
     }
 
-    match input {
-        KeyEvent {
-            code: KeyCode::Esc, ..
-        } => {
+    match action {
+        Some(Action::NormalMode) => {
+            // Escape rejects the suggestion under review, or all of them once
+            // the command line has been cleared
+            if textarea.is_empty() {
+                state.suggestions.clear();
+            } else if !state.suggestions.is_empty() {
+                state.suggestions.remove(0);
+            }
+
             textarea.delete_line_by_head();
             state.deselect_rows();
+            load_suggestion(state, textarea);
         }
-
-        KeyEvent {
-            code: KeyCode::Backspace,
-            modifiers: KeyModifiers::ALT,
-            ..
-        } => {
+        Some(Action::DeleteWord) => {
             textarea.delete_word();
         }
-        KeyEvent {
-            code: KeyCode::Left,
-            modifiers: KeyModifiers::ALT,
-            ..
-        } => textarea.move_cursor(CursorMove::WordBack),
-
-        KeyEvent {
-            code: KeyCode::Right,
-            modifiers: KeyModifiers::ALT,
-            ..
-        } => textarea.move_cursor(CursorMove::WordForward),
-        KeyEvent {
-            code: KeyCode::Enter,
-            ..
-        } => {
-            let selected = state.selected_rows.clone();
+        Some(Action::WordBack) => textarea.move_cursor(CursorMove::WordBack),
+        Some(Action::WordForward) => textarea.move_cursor(CursorMove::WordForward),
+        Some(Action::RunCommand) => {
+            let selected = state.selected_rows();
 
             let ids = selected
                 .iter()
                 .filter_map(|index| state.notes.get(*index).map(|note| note.id.clone()))
                 .collect();
 
-            match textarea.lines().join("").get(0..1).unwrap() {
-                "v" => state.upvote(&ids),
-                "d" => state.unvote(&ids),
+            match Command::parse(&textarea.lines().join("")) {
+                Some((Command::Upvote, _, _)) => state.upvote(&ids),
+                Some((Command::Downvote, _, _)) => state.unvote(&ids),
+                Some((Command::Group, _, args)) => {
+                    let name = (!args.is_empty()).then(|| args.join(" "));
+                    state.group(&selected, name);
+
+                    if !state.suggestions.is_empty() {
+                        state.suggestions.remove(0);
+                        load_suggestion(state, textarea);
+                    }
+                }
+                Some((Command::Ungroup, _, _)) => state.ungroup(&selected),
+                Some((Command::Suggest, _, _)) => {
+                    state.suggest_groups();
+                    load_suggestion(state, textarea);
+                }
+                Some((Command::ActionItem, _, _)) => state.toggle_action_items(&selected),
+                Some((Command::Done, _, _)) => state.toggle_done(&selected),
+                Some((Command::Sort, _, args)) => match Sort::from_args(&args) {
+                    Ok(sort) => state.set_sort(sort),
+                    Err(e) => state.notify(Level::Warning, e.to_string()),
+                },
+                Some((Command::Export, _, _)) => match state.export() {
+                    Ok(path) => {
+                        state.notify(Level::Info, format!("exported to {}", path.display()))
+                    }
+                    Err(e) => state.notify(Level::Error, format!("{e:#}")),
+                },
+                Some((Command::Messages, _, _)) => state.show_messages = !state.show_messages,
+                Some((Command::React, _, args)) => {
+                    match args.first().map(|arg| arg.parse::<Reaction>()) {
+                        Some(Ok(reaction)) => state.react(&selected, reaction),
+                        Some(Err(e)) => state.notify(Level::Warning, e.to_string()),
+                        None => state.notify(Level::Warning, "react with what?"),
+                    }
+                }
+                None if !textarea.is_empty() => {
+                    let line = textarea.lines().join("");
+                    let name = line.split_whitespace().next().unwrap_or_default();
+                    state.notify(Level::Warning, format!("unknown command `{name}`"));
+                }
                 _ => {}
             }
         }
-
-        input => {
+        Some(_) => {}
+        None => {
             textarea.input(input);
         }
     };
 
-    if let Some((command, indices_str)) = textarea
-        .lines()
-        .first()
-        .unwrap()
-        .as_str()
-        .split_once(char::is_whitespace)
-    {
-        match command {
-            "group" | "g" => {
-                let indicies = get_indicies(indices_str.to_owned());
-                state.select_rows(&indicies);
-            }
-            "vote" | "v" => {
-                let indicies = get_indicies(indices_str.to_owned());
-                state.select_rows(&indicies);
-            }
-            _ => {}
-        }
+    if let Some((_, indices, _)) = Command::parse(textarea.lines().first().unwrap()) {
+        state.select_rows(&indices);
     }
+}
+
+/// Type pasted text into the command box, on a single line. Returns false
+/// outside of command mode.
+pub fn handle_paste(text: &str, state: &mut State, textarea: &mut TextArea<'_>) -> bool {
+    if state.mode != Mode::Command {
+        return false;
+    }
+
+    textarea.insert_str(text.lines().collect::<Vec<&str>>().join(" "));
+    if let Some((_, indices, _)) = Command::parse(textarea.lines().first().unwrap()) {
+        state.select_rows(&indices);
+    }
+    true
+}
+
+/// Put the suggestion under review in the command box as a group command, so
+/// it can be tweaked before it's accepted
+fn load_suggestion(state: &mut State, textarea: &mut TextArea<'_>) {
+    if let Some(indices) = state.current_suggestion() {
+        let indices: Vec<String> = indices.iter().map(usize::to_string).collect();
 
-    fn get_indicies(indices: String) -> Vec<usize> {
-        indices
-            .split(char::is_whitespace)
-            .map(|it| it.parse())
-            .filter_map(|it| it.ok())
-            .collect()
+        textarea.move_cursor(CursorMove::End);
+        textarea.delete_line_by_head();
+        textarea.insert_str(format!("g {}", indices.join(" ")));
     }
 }
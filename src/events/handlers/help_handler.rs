@@ -1,13 +1,31 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::app::{keymap::Action, state::State};
 
-use crate::app::state::State;
-
-pub fn handle_show_help(input: KeyEvent, state: &mut State) {
-    if let KeyEvent {
-        code: KeyCode::Char('?'),
-        ..
-    } = input
-    {
+pub fn handle_show_help(action: Option<Action>, state: &mut State) {
+    if let Some(Action::ToggleHelp) = action {
         state.show_help = !state.show_help;
     }
 }
+
+pub fn handle_show_participants(action: Option<Action>, state: &mut State) {
+    if let Some(Action::ToggleParticipants) = action {
+        state.show_participants = !state.show_participants;
+    }
+}
+
+pub fn handle_show_dashboard(action: Option<Action>, state: &mut State) {
+    if let Some(Action::ToggleDashboard) = action {
+        state.show_dashboard = !state.show_dashboard;
+    }
+}
+
+pub fn handle_show_trends(action: Option<Action>, state: &mut State) {
+    if let Some(Action::ToggleTrends) = action {
+        state.toggle_trends();
+    }
+}
+
+pub fn handle_compact(action: Option<Action>, state: &mut State) {
+    if let Some(Action::ToggleCompact) = action {
+        state.compact = !state.compact;
+    }
+}
@@ -1,49 +1,92 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::KeyEvent;
 use tui_textarea::{CursorMove, TextArea};
 
-use crate::{
-    app::{mode::Mode, note::Note, state::State},
-    network::actions::NetworkAction,
+use crate::app::{
+    keymap::Action,
+    mode::Mode,
+    note::{Note, MAX_LENGTH},
+    notification::Level,
+    state::State,
 };
 
-pub fn handle_insert(input: KeyEvent, state: &mut State, textarea: &mut TextArea<'_>) {
+/// Type pasted text into the editor, keeping its line breaks so it's
+/// published as one note. Returns false outside of insert mode.
+pub fn handle_paste(text: &str, state: &State, textarea: &mut TextArea<'_>) -> bool {
     if state.mode != Mode::Insert {
+        return false;
+    }
+
+    for (number, line) in text.split('\n').enumerate() {
+        if number > 0 {
+            textarea.insert_newline();
+        }
+        textarea.insert_str(line);
+    }
+    true
+}
+
+pub fn handle_insert(
+    action: Option<Action>,
+    input: KeyEvent,
+    state: &mut State,
+    textarea: &mut TextArea<'_>,
+) {
+    // Editing starts from the detail popup, with the note's text in the editor
+    if let Some(Action::EditNote) = action {
+        if let Some(text) = state.start_editing() {
+            *textarea = TextArea::from(text.lines());
+            textarea.move_cursor(CursorMove::Bottom);
+            textarea.move_cursor(CursorMove::End);
+        }
         return;
     }
 
-    match input {
-        KeyEvent {
-            code: KeyCode::Backspace,
-            modifiers: KeyModifiers::ALT,
-            ..
-        } => {
+    if state.mode != Mode::Insert {
+        return;
+    }
+
+    match action {
+        Some(Action::DeleteWord) => {
             textarea.delete_word();
         }
-        KeyEvent {
-            code: KeyCode::Left,
-            modifiers: KeyModifiers::ALT,
-            ..
-        } => textarea.move_cursor(CursorMove::WordBack),
-
-        KeyEvent {
-            code: KeyCode::Right,
-            modifiers: KeyModifiers::ALT,
-            ..
-        } => textarea.move_cursor(CursorMove::WordForward),
-        KeyEvent {
-            code: KeyCode::Enter,
-            ..
-        } => {
-            if !textarea.is_empty() {
-                let text = textarea.lines().join("\n");
-                state.dispatch(NetworkAction::PublishNote(Note::new(
-                    state.display_name.clone(),
-                    text,
-                )));
+        Some(Action::WordBack) => textarea.move_cursor(CursorMove::WordBack),
+        Some(Action::WordForward) => textarea.move_cursor(CursorMove::WordForward),
+        Some(Action::NewLine) => textarea.insert_newline(),
+        Some(Action::NormalMode) if state.editing.is_some() => {
+            state.editing = None;
+            *textarea = TextArea::default();
+        }
+        Some(Action::PublishNote) => {
+            let text = textarea.lines().join("\n");
+
+            let writing_note = state.open_note.is_none() || state.editing.is_some();
+
+            // Notes over the limit stay in the editor, so they can be cut down
+            if text.trim().is_empty() {
+                return;
+            }
+            let length = text.chars().count();
+            if writing_note && length > MAX_LENGTH {
+                state.notify(
+                    Level::Warning,
+                    format!("the note is {length} characters, {MAX_LENGTH} at most"),
+                );
+                return;
+            }
+
+            if state.editing.is_some() {
+                state.edit_note(text);
+                state.mode = Mode::Detail;
+            } else if state.open_note.is_some() {
+                state.add_comment(text);
+            } else {
+                let note = Note::new(state.display_name.clone(), text, state.classifier.as_ref());
+                state.publish_note(note);
             }
-            textarea.delete_line_by_head();
+            *textarea = TextArea::default();
         }
-        input => {
+        Some(_) => {}
+        None => {
             textarea.input(input);
         }
     };
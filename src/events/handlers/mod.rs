@@ -3,21 +3,147 @@ use std::sync::MutexGuard;
 use crossterm::event::KeyEvent;
 use tui_textarea::TextArea;
 
-use crate::app::state::State;
+use crate::app::{
+    keymap::{Action, KeyChord, KeyResult},
+    state::State,
+};
 
 pub mod command_handler;
 pub mod help_handler;
 pub mod insert_handler;
 pub mod mode_handler;
+pub mod mouse_handler;
+pub mod note_handler;
+pub mod reaction_handler;
 
+/// Handle a key press, returns the action it triggered, if any
 pub fn handle_input(
     input: KeyEvent,
     state: &mut MutexGuard<'_, State>,
     textarea: &mut TextArea<'_>,
     command_textarea: &mut TextArea<'_>,
-) {
-    help_handler::handle_show_help(input, state);
-    insert_handler::handle_insert(input, state, textarea);
-    command_handler::handle_command(input, state, command_textarea);
-    mode_handler::handle_mode(input, state);
+) -> Option<Action> {
+    state.touch();
+
+    if state.show_reaction_picker {
+        reaction_handler::handle_reaction(input, state);
+        return None;
+    }
+
+    state.pending_keys.push(input);
+    let keys: Vec<KeyChord> = state.pending_keys.iter().map(|key| (*key).into()).collect();
+
+    match state.keymap.resolve(&state.mode, &keys) {
+        KeyResult::Pending => None,
+        KeyResult::Action(action) => {
+            state.pending_keys.clear();
+            handle_action(Some(action), input, state, textarea, command_textarea)
+        }
+        KeyResult::Unbound => {
+            let mut pending = std::mem::take(&mut state.pending_keys).into_iter();
+            let first = pending.next().unwrap_or(input);
+
+            // An abandoned sequence: its first key means nothing on its own,
+            // the others are pressed again, they might still mean something
+            let mut action = handle_action(None, first, state, textarea, command_textarea);
+            for key in pending {
+                action = handle_input(key, state, textarea, command_textarea).or(action);
+            }
+            action
+        }
+    }
+}
+
+fn handle_action(
+    action: Option<Action>,
+    input: KeyEvent,
+    state: &mut MutexGuard<'_, State>,
+    textarea: &mut TextArea<'_>,
+    command_textarea: &mut TextArea<'_>,
+) -> Option<Action> {
+    help_handler::handle_show_help(action, state);
+    help_handler::handle_show_participants(action, state);
+    help_handler::handle_show_dashboard(action, state);
+    help_handler::handle_show_trends(action, state);
+    help_handler::handle_compact(action, state);
+    insert_handler::handle_insert(action, input, state, textarea);
+    command_handler::handle_command(action, input, state, command_textarea);
+    note_handler::handle_note(action, state);
+    mode_handler::handle_mode(action, state);
+
+    action
+}
+
+/// Handle pasted text, it's typed into the note editor or the command box
+/// as is, without any of its newlines triggering key bindings
+pub fn handle_paste(
+    text: &str,
+    state: &mut State,
+    textarea: &mut TextArea<'_>,
+    command_textarea: &mut TextArea<'_>,
+) -> bool {
+    if state.show_reaction_picker {
+        return false;
+    }
+    state.touch();
+
+    // Terminals send newlines as carriage returns
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let text = text.trim_end_matches('\n');
+
+    insert_handler::handle_paste(text, state, textarea)
+        || command_handler::handle_paste(text, state, command_textarea)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+    use crate::app::{keymap::Keymap, mode::Mode};
+
+    /// Type the keys in insert mode, with `j k` bound to normal mode
+    fn type_keys(keys: &str) -> (Vec<String>, Mode) {
+        let (sender, _receiver) = unbounded_channel();
+        let state = Mutex::new(State::new(sender, "ann".to_string()));
+        let mut state = state.lock().unwrap();
+        state.keymap = Keymap::with_overrides(&HashMap::from([(
+            "insert.normal_mode".to_string(),
+            "j k".to_string(),
+        )]))
+        .unwrap();
+        state.mode = Mode::Insert;
+
+        let mut textarea = TextArea::default();
+        let mut command_textarea = TextArea::default();
+        for c in keys.chars() {
+            handle_input(
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+                &mut state,
+                &mut textarea,
+                &mut command_textarea,
+            );
+        }
+
+        (textarea.lines().to_vec(), state.mode.clone())
+    }
+
+    #[test]
+    fn completed_sequences_trigger_their_action() {
+        assert_eq!(type_keys("ajk"), (vec!["a".to_string()], Mode::Normal));
+    }
+
+    #[test]
+    fn abandoned_sequences_type_every_key() {
+        assert_eq!(type_keys("jx"), (vec!["jx".to_string()], Mode::Insert));
+        assert_eq!(type_keys("jxj"), (vec!["jx".to_string()], Mode::Insert));
+    }
+
+    #[test]
+    fn replayed_keys_can_start_a_sequence() {
+        assert_eq!(type_keys("jjk"), (vec!["j".to_string()], Mode::Normal));
+    }
 }
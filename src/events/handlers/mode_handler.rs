@@ -1,30 +1,28 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::app::{keymap::Action, mode::Mode, state::State};
 
-use crate::app::{mode::Mode, state::State};
-
-pub fn handle_mode(input: KeyEvent, state: &mut State) {
-    match state.mode {
-        Mode::Normal => match input {
-            KeyEvent {
-                code: KeyCode::Char('i'),
-                ..
-            } => state.mode = Mode::Insert,
-
-            KeyEvent {
-                code: KeyCode::Char(':'),
-                ..
-            } => state.mode = Mode::Command,
+pub fn handle_mode(action: Option<Action>, state: &mut State) {
+    match action {
+        Some(Action::InsertMode) | Some(Action::Comment) => state.mode = Mode::Insert,
+        Some(Action::EditNote) if state.editing.is_some() => state.mode = Mode::Insert,
+        Some(Action::CommandMode) => state.mode = Mode::Command,
+        Some(Action::NormalMode) => {
+            // Stay around while there are suggested groups left to review
+            if state.mode == Mode::Command && !state.suggestions.is_empty() {
+                return;
+            }
 
-            _ => {}
-        },
-        _ => {
-            if let KeyEvent {
-                code: KeyCode::Esc, ..
-            } = input
-            {
-                state.mode = Mode::Normal;
-                state.deselect_rows();
+            // Leaving a comment goes back to the note it was written on
+            if state.mode == Mode::Insert && state.open_note.is_some() {
+                state.mode = Mode::Detail;
+                return;
             }
+
+            state.mode = Mode::Normal;
+            state.show_messages = false;
+            state.deselect_rows();
+            state.close_note();
+            state.resort();
         }
+        _ => {}
     };
 }
@@ -0,0 +1,98 @@
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use tui::layout::Rect;
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    app::{mode::Mode, state::State},
+    ui::{
+        layout::contains,
+        note_detail::{detail_area, vote_badge},
+        notes_list::NotesView,
+    },
+};
+
+/// Handle a click or a turn of the wheel, `screen` is the size of the
+/// terminal as of the last draw. Returns true if anything changed.
+pub fn handle_mouse(
+    event: MouseEvent,
+    state: &mut State,
+    view: &mut NotesView,
+    screen: Rect,
+    command_textarea: &mut TextArea<'_>,
+) -> bool {
+    // Popups other than the open note don't take the mouse
+    if state.show_reaction_picker || state.show_messages || state.show_trends {
+        return false;
+    }
+    state.touch();
+
+    let (column, row) = (event.column, event.row);
+
+    if state.open_note.is_some() {
+        let on_votes = vote_badge(detail_area(screen), state)
+            .is_some_and(|badge| contains(badge, column, row));
+        if event.kind == MouseEventKind::Down(MouseButton::Left) && on_votes {
+            state.toggle_vote_open_note();
+            return true;
+        }
+        return false;
+    }
+
+    match event.kind {
+        MouseEventKind::ScrollUp => view.scroll(-1),
+        MouseEventKind::ScrollDown => view.scroll(1),
+        MouseEventKind::Down(MouseButton::Left) => {
+            let Some(index) = view.note_at(column, row) else {
+                return false;
+            };
+            let multi_select = event.modifiers.contains(KeyModifiers::CONTROL);
+
+            match state.mode {
+                Mode::Normal if multi_select => toggle_selected(index, state, command_textarea),
+                Mode::Normal if view.votes_at(column, row) == Some(index) => {
+                    let id = state.notes[index].id.clone();
+                    state.toggle_vote(id);
+                }
+                // Clicking the note under the cursor opens it
+                Mode::Normal if state.cursor == index => state.open_selected_note(),
+                Mode::Normal => state.cursor = index,
+                Mode::Command => toggle_selected(index, state, command_textarea),
+                _ => return false,
+            }
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+/// Add the note to the ones the command runs on, or take it out if it's
+/// already in there. The indices in the command line are rewritten to match,
+/// with the cursor in front of them when there's no command yet.
+fn toggle_selected(index: usize, state: &mut State, textarea: &mut TextArea<'_>) {
+    let mut selected = state.selected_rows();
+    match selected.iter().position(|row| *row == index) {
+        Some(position) => {
+            selected.remove(position);
+        }
+        None => selected.push(index),
+    }
+
+    let line = textarea.lines().first().cloned().unwrap_or_default();
+    let mut words = line
+        .split_whitespace()
+        .filter(|word| word.parse::<usize>().is_err());
+    let name = words.next().unwrap_or_default();
+    let indices: Vec<String> = selected.iter().map(usize::to_string).collect();
+    let args: Vec<&str> = words.collect();
+
+    textarea.move_cursor(CursorMove::End);
+    textarea.delete_line_by_head();
+    textarea.insert_str(format!("{name} {} {}", indices.join(" "), args.join(" ")).trim_end());
+    if name.is_empty() {
+        textarea.move_cursor(CursorMove::Head);
+    }
+
+    state.select_rows(&selected);
+    state.mode = Mode::Command;
+}
@@ -0,0 +1,13 @@
+use crate::app::{keymap::Action, state::State};
+
+pub fn handle_note(action: Option<Action>, state: &mut State) {
+    match action {
+        Some(Action::CursorUp) => state.move_cursor(-1),
+        Some(Action::CursorDown) => state.move_cursor(1),
+        Some(Action::OpenNote) => state.open_selected_note(),
+        Some(Action::React) => state.show_reaction_picker = state.reaction_target().is_some(),
+        Some(Action::ToggleVote) => state.toggle_vote_open_note(),
+        Some(Action::DeleteNote) => state.delete_open_note(),
+        _ => {}
+    }
+}
@@ -0,0 +1,20 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::app::{reaction::Reaction, state::State};
+
+/// Handle a key while the reaction picker is open, a number picks that
+/// reaction and any other key closes the picker.
+pub fn handle_reaction(input: KeyEvent, state: &mut State) {
+    state.show_reaction_picker = false;
+
+    let picked = match input.code {
+        KeyCode::Char(c) => c
+            .to_digit(10)
+            .and_then(|n| Reaction::ALL.get((n as usize).checked_sub(1)?)),
+        _ => None,
+    };
+
+    if let (Some(reaction), Some(index)) = (picked, state.reaction_target()) {
+        state.react(&[index], *reaction);
+    }
+}
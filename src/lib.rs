@@ -15,6 +15,9 @@
 /// CLI param parsing
 pub mod cli;
 
+/// User config file
+pub mod config;
+
 /// App
 pub mod app;
 
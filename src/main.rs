@@ -1,41 +1,91 @@
-use core::panic::PanicInfo;
-use std::io::stdout;
-use std::panic;
-use std::sync::mpsc::Receiver;
+use std::io::{stdin, stdout, Write};
+use std::panic::{self, PanicHookInfo};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use chrono::{TimeZone, Utc};
 use crossterm::cursor::RestorePosition;
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent};
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
 };
 use crossterm::{execute, ExecutableCommand};
 use retro::events::{
     event::{Event, Events},
-    handlers::handle_input,
+    handlers::{handle_input, handle_paste, mouse_handler::handle_mouse},
 };
-use retro::ui::command_textbox::command_textbox;
-use retro::ui::help::help;
-use retro::ui::new_note::new_note;
+use retro::ui::command_textbox::{command_block, command_textbox};
+use retro::ui::dashboard::dashboard;
+use retro::ui::help::{help, shortcuts};
+use retro::ui::layout::{too_small, Areas, MIN_HEIGHT, MIN_WIDTH};
+use retro::ui::new_note::{new_note, note_block};
+use retro::ui::note_detail::{centered, centered_size, detail_area, note_detail};
+use retro::ui::notifications::{messages, toast, TOAST_HEIGHT};
+use retro::ui::participants::participants;
+use retro::ui::reaction_picker::reaction_picker;
 use retro::ui::room_info::room_info;
+use retro::ui::trends::trends;
 use retro::{
-    app::{mode::Mode, state::State},
-    cli::RetroArgs,
-    network::{actions::NetworkAction, remote::Remote},
-    ui::{notes_list::notes_list, status_bar::status_bar},
+    app::{
+        keymap::{Action, Keymap},
+        lexicon::Lexicon,
+        mode::Mode,
+        room::Room,
+        state::State,
+        stats::RoomStats,
+        theme::Theme,
+    },
+    cli::{RetroArgs, RoomCommand},
+    network::{actions::NetworkAction, backend::Backend, journal::Journal, remote::Remote},
+    ui::{
+        notes_list::{notes_list, NotesView},
+        status_bar::status_bar,
+    },
+};
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver},
+    Notify,
 };
 use tui::backend::CrosstermBackend;
 use tui::layout::Rect;
-use tui::widgets::Paragraph;
+use tui::widgets::{Clear, Paragraph};
 use tui::Terminal;
 use tui_textarea::TextArea;
 
-#[tokio::main]
-async fn start_tokio(io_rx: Receiver<NetworkAction>, network: &Remote) {
-    while let Ok(event) = io_rx.recv() {
-        let _ = network.handle_event(event).await;
+/// How often the UI wakes up on its own, for heartbeats and whatever on
+/// screen changes with time. Nothing is drawn unless something did change.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// How long quitting waits for the changes still on their way to the
+/// backend, the queued ones that don't make it go to the journal
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Send the actions to the backend one at a time, in the order they were
+/// dispatched, and wake the UI up after each of them. Once `quitting` is
+/// notified nothing new is taken, and it returns when the queue is empty.
+async fn start_network(
+    io_rx: &mut UnboundedReceiver<NetworkAction>,
+    network: &Remote<'_>,
+    changed: &Notify,
+    quitting: &Notify,
+) {
+    loop {
+        let action = tokio::select! {
+            action = io_rx.recv() => action,
+            _ = quitting.notified() => {
+                io_rx.close();
+                io_rx.recv().await
+            }
+        };
+        let Some(action) = action else {
+            return;
+        };
+
+        network.run(action).await;
+        changed.notify_one();
     }
 }
 
@@ -46,29 +96,251 @@ async fn main() -> Result<()> {
     }));
 
     let args = RetroArgs::new();
-    let (sync_io_tx, sync_io_rx) = std::sync::mpsc::channel::<NetworkAction>();
-    let mut textarea = new_note();
+    let backend = Backend::new(args.project_id.clone(), args.endpoint.clone());
+
+    let password = match &args.command {
+        Some(RoomCommand::Join { password, .. }) => password.clone(),
+        Some(command) => return run_room_command(command, &backend).await,
+        None => None,
+    };
+
+    let room_id = args
+        .room()
+        .cloned()
+        .ok_or_else(|| anyhow!("no room given, use `retro join <room>`"))?;
+    let display_name = args
+        .display_name
+        .clone()
+        .ok_or_else(|| anyhow!("no display name given, use `--display-name <name>`"))?;
+
+    let room = backend.get_room(&room_id).await?;
+    if let Some(room) = &room {
+        if room.archived {
+            bail!("room {room_id} is archived");
+        }
+        if !room.accepts(password.as_deref()) {
+            bail!("wrong password for room {room_id}");
+        }
+    }
+
+    let keymap = Keymap::with_overrides(&args.config.keybindings)?;
+    let theme = Theme::load(args.config.theme.as_deref(), &args.config.themes)?;
+    let lexicon = match &args.config.lexicon {
+        Some(path) => Lexicon::with_file(path)?,
+        None => Lexicon::default(),
+    };
+
+    let journal = match Journal::default_path(&room_id)? {
+        Some(path) => Journal::open(&path)?,
+        None => Journal::default(),
+    };
+
+    let (io_tx, mut io_rx) = unbounded_channel::<NetworkAction>();
+    let mut textarea = new_note(&theme);
+
+    let state = Arc::new(Mutex::new(State::new(io_tx, display_name)));
+
+    {
+        let mut state = state.lock().expect("cannot do stuff");
+        state.keymap = keymap;
+        state.theme = theme;
+        state.classifier = Arc::new(lexicon);
+        state.compact = args.config.compact;
+        state.relative_time = args.config.relative_time;
+        state.room_id = room_id.clone();
+        state.export_dir = args.config.export_dir.clone();
+        state.set_journal(journal);
+        if let Some(room) = &room {
+            state.set_room(room);
+        }
+        state.dispatch(NetworkAction::GetNotes);
+        state.join();
+    }
+
+    let network = Remote::new(backend, &room_id, &state);
+    let changed = Notify::new();
+    let quitting = Notify::new();
+
+    // The network loop never finishes on its own, the state keeps the
+    // channel open. Once the UI quits it gets a while to send what's left.
+    let result = {
+        let network_loop = start_network(&mut io_rx, &network, &changed, &quitting);
+        tokio::pin!(network_loop);
+
+        let result = tokio::select! {
+            result = start_ui(&room_id, &state, &mut textarea, &changed) => result,
+            _ = &mut network_loop => Ok(()),
+        };
+        // The terminal is given back whether the UI quit or failed
+        let restored = quit();
+
+        quitting.notify_one();
+        let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, network_loop).await;
+        result.and(restored)
+    };
 
-    let state = Arc::new(Mutex::new(State::new(sync_io_tx, args.clone())));
+    // Whatever wasn't sent in time is sent the next time the room is joined
+    let session_id = {
+        let mut state = state.lock().expect("cannot do stuff");
+        while let Ok(action) = io_rx.try_recv() {
+            if Journal::accepts(&action) {
+                let _ = state.journal.push(action);
+            }
+        }
+        state.session_id.clone()
+    };
 
-    state
-        .lock()
-        .expect("cannot do stuff")
-        .dispatch(NetworkAction::GetNotes);
+    // Leave from here, so the others see us go
+    let _ = network
+        .handle_event(NetworkAction::LeaveRetro(session_id))
+        .await;
 
-    let cloned_state = Arc::clone(&state);
-    let cloned_args = args.clone();
+    result
+}
 
-    std::thread::spawn(move || {
-        let network = Remote::new(&cloned_args.room, &state);
-        start_tokio(sync_io_rx, &network);
-    });
+async fn run_room_command(command: &RoomCommand, backend: &Backend) -> Result<()> {
+    match command {
+        RoomCommand::Create {
+            name,
+            template,
+            votes,
+            password,
+            series,
+            anonymous,
+        } => {
+            let mut room = Room::new(
+                name.clone(),
+                *template,
+                *votes,
+                password.as_deref(),
+                series.clone(),
+            );
+            room.anonymous = *anonymous;
+            let id = backend.create_room(&room).await?;
+            println!("Created room {name}, join it with:\n\n  retro join {id}");
+        }
+        RoomCommand::List { all } => {
+            for room in backend.list_rooms(*all).await? {
+                let created = Utc
+                    .timestamp_opt(room.created_at, 0)
+                    .single()
+                    .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                let votes = room
+                    .vote_budget
+                    .map(|budget| format!("{budget} votes"))
+                    .unwrap_or_else(|| "unlimited votes".to_string());
+                let anonymous = if room.anonymous { ", anonymous" } else { "" };
+                let archived = if room.archived { " (archived)" } else { "" };
 
-    start_ui(args, &cloned_state, &mut textarea).await?;
+                println!(
+                    "{:<22} {:<20} {:<20} {created}  {votes}{anonymous}{archived}",
+                    room.id, room.name, room.template
+                );
+            }
+        }
+        RoomCommand::Archive { room, password } => {
+            unlock_room(backend, room, password.as_deref()).await?;
+            backend.archive_room(room).await?;
+            println!("Archived room {room}");
+        }
+        RoomCommand::Delete {
+            room,
+            password,
+            yes,
+        } => {
+            let name = unlock_room(backend, room, password.as_deref()).await?.name;
+            if !yes
+                && !confirm(&format!(
+                    "Delete room {name} ({room}) and all of its notes?"
+                ))?
+            {
+                println!("Kept room {room}");
+                return Ok(());
+            }
+
+            backend.delete_room(room).await?;
+            println!("Deleted room {room}");
+        }
+        RoomCommand::Stats { series, csv } => {
+            let stats = backend.series_stats(series).await?;
+
+            if *csv {
+                println!("{}", RoomStats::CSV_HEADER);
+                for room in &stats {
+                    println!("{}", room.to_csv());
+                }
+                return Ok(());
+            }
+
+            println!("{series}, {} retros\n", stats.len());
+            println!(
+                "{:<20} {:<10} {:>5} {:>6}  {:<16} {:<16}",
+                "room", "date", "notes", "people", "happy", "actions done"
+            );
+            for room in &stats {
+                let date = Utc
+                    .timestamp_opt(room.created_at, 0)
+                    .single()
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+
+                println!(
+                    "{:<20} {date:<10} {:>5} {:>6}  {:<16} {:<16}",
+                    room.name,
+                    room.notes,
+                    room.participants,
+                    bar(room.sentiment_ratio()),
+                    bar(room.completion_rate()),
+                );
+            }
+        }
+        RoomCommand::Join { .. } => {}
+    }
 
     Ok(())
 }
 
+/// A percentage followed by a bar of up to ten blocks, such as `60% ██████`
+fn bar(ratio: Option<f64>) -> String {
+    const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+    let Some(ratio) = ratio else {
+        return "   -".to_string();
+    };
+
+    let eighths = (ratio * 80.0).round() as usize;
+    format!(
+        "{:>3.0}% {}{}",
+        ratio * 100.0,
+        "█".repeat(eighths / 8),
+        EIGHTHS[eighths % 8]
+    )
+}
+
+/// The room, if it exists and the password lets you in
+async fn unlock_room(backend: &Backend, room_id: &str, password: Option<&str>) -> Result<Room> {
+    let room = backend
+        .get_room(room_id)
+        .await?
+        .ok_or_else(|| anyhow!("room {room_id} doesn't exist"))?;
+    if !room.accepts(password) {
+        bail!("wrong password for room {room_id}");
+    }
+
+    Ok(room)
+}
+
+/// Ask a yes or no question on the terminal, anything but yes is a no
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
 fn quit() -> Result<()> {
     disable_raw_mode()?;
     let mut stdout = std::io::stdout();
@@ -76,104 +348,183 @@ fn quit() -> Result<()> {
         stdout,
         LeaveAlternateScreen,
         DisableMouseCapture,
+        DisableBracketedPaste,
         RestorePosition
     )?;
 
     Ok(())
 }
 
-fn panic_hook(info: &PanicInfo<'_>) {
+fn panic_hook(info: &PanicHookInfo<'_>) {
     dbg!(info);
     let _ = quit();
 }
 
 async fn start_ui(
-    args: RetroArgs,
-    state: &Arc<Mutex<State>>,
+    room_id: &String,
+    shared_state: &Arc<Mutex<State>>,
     textarea: &mut TextArea<'static>,
+    changed: &Notify,
 ) -> Result<()> {
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     enable_raw_mode()?;
 
-    let mut command_textarea = command_textbox();
+    let mut command_textarea =
+        command_textbox(&shared_state.lock().expect("Could not lock state").theme);
 
     let mut backend = CrosstermBackend::new(stdout);
-    backend.execute(SetTitle(args.room.clone()))?;
+    backend.execute(SetTitle(room_id))?;
 
-    let events = Events::new(Duration::from_millis(8));
+    let mut events = Events::new(TICK_RATE);
 
     let mut terminal = Terminal::new(backend)?;
+    let mut notes_view = NotesView::default();
+
+    // Only draw when something changed, an idle client does next to nothing
+    let mut dirty = true;
 
     loop {
         let size = terminal.size()?;
-        let mut state = state.lock().expect("Could not lock state");
+        let fits = size.width >= MIN_WIDTH && size.height >= MIN_HEIGHT;
+        // Only held while drawing and handling input, so the network can
+        // update the state in between
+        if dirty {
+            let state = shared_state.lock().expect("Could not lock state");
 
-        terminal.draw(|ui| {
-            // Notes list
-            ui.render_widget(
-                notes_list(&state),
-                Rect::new(0, 0, size.width, size.height - 1),
-            );
+            terminal.draw(|ui| {
+                if !fits {
+                    ui.render_widget(too_small(size), size);
+                    return;
+                }
+                let areas = Areas::new(size, state.show_dashboard, state.show_participants);
 
-            // Mode info
-            ui.render_widget(status_bar(&state), Rect::new(0, size.height - 1, 5, 1));
-            ui.render_widget(
-                room_info(&state.display_name, &args.room),
-                Rect::new(6, size.height - 1, 30, 1),
-            );
-            ui.render_widget(
-                Paragraph::new(format!("{} participants", &state.participants.len())),
-                Rect::new(size.width - 17, size.height - 1, 16, 1),
-            );
+                notes_list(ui, areas.notes, &state, &mut notes_view);
 
-            ui.render_widget(
-                Paragraph::new(state.tick_count.to_string()),
-                Rect::new(0, 0, 10, 1),
-            );
+                if let Some(area) = areas.dashboard {
+                    dashboard(ui, area, &state);
+                }
 
-            if state.show_help {
-                ui.render_widget(
-                    help(&state),
-                    Rect::new(
-                        size.width - size.width / 3 - 4,
-                        size.height - 15,
-                        size.width / 3,
-                        12,
-                    ),
-                );
-            }
+                if let Some(area) = areas.participants {
+                    ui.render_widget(participants(&state), area);
+                }
 
-            if state.mode == Mode::Insert {
+                // Mode info
+                ui.render_widget(status_bar(&state), areas.mode);
+                ui.render_widget(room_info(&state.display_name, room_id), areas.room);
                 ui.render_widget(
-                    textarea.widget(),
-                    Rect::new(1, size.height / 4 * 3, size.width - 2, size.height / 4 - 2),
+                    Paragraph::new(format!("{} participants", &state.participants.len())),
+                    areas.participant_count,
                 );
-            }
+                let mut progress = vec![];
+                if state.offline {
+                    progress.push("offline".to_string());
+                }
+                if let Some(loaded) = state.loading_notes {
+                    progress.push(format!("loading notes… {loaded}"));
+                }
+                if !state.journal.is_empty() {
+                    progress.push(format!("{} to sync", state.journal.len()));
+                }
+                ui.render_widget(Paragraph::new(progress.join("  ")), areas.progress);
 
-            if state.mode == Mode::Command {
-                ui.render_widget(
-                    command_textarea.widget(),
-                    Rect::new(0, size.height - 4, size.width, 3),
-                )
-            }
-        })?;
-
-        match events.next()? {
-            Event::Input(i) => {
-                if let (
-                    KeyEvent {
-                        code: KeyCode::Char('q'),
-                        ..
-                    },
-                    Mode::Normal,
-                ) = (i, &state.mode)
+                if state.open_note.is_some() {
+                    let area = detail_area(size);
+                    ui.render_widget(Clear, area);
+                    ui.render_widget(note_detail(&state), area);
+                }
+
+                if state.show_trends {
+                    let area = centered(size, 70, 70);
+                    ui.render_widget(Clear, area);
+                    trends(ui, area, &state);
+                }
+
+                if state.show_reaction_picker {
+                    let area = centered_size(size, 32, 3);
+                    ui.render_widget(Clear, area);
+                    ui.render_widget(reaction_picker(&state), area);
+                }
+
+                if state.show_help {
+                    ui.render_widget(help(&state), areas.help(shortcuts(&state).len()));
+                }
+
+                if state.mode == Mode::Insert {
+                    textarea.set_block(note_block(&state, textarea));
+                    ui.render_widget(textarea.widget(), areas.editor);
+                }
+
+                if state.mode == Mode::Command {
+                    command_textarea.set_block(command_block(&state));
+                    ui.render_widget(command_textarea.widget(), areas.command)
+                }
+
+                if state.show_messages {
+                    let area = centered(size, 70, 60);
+                    ui.render_widget(Clear, area);
+                    ui.render_widget(messages(&state), area);
+                }
+
+                // Toasts stack up from just above the status bar, or the command
+                // box when it's open
+                let mut bottom = match state.mode {
+                    Mode::Command => areas.command.y,
+                    _ => areas.main.bottom(),
+                };
+                let width = size.width.min(50);
+                for notification in state.toasts().into_iter().rev() {
+                    if bottom < TOAST_HEIGHT {
+                        break;
+                    }
+                    bottom -= TOAST_HEIGHT;
+
+                    let area = Rect::new(size.width - width, bottom, width, TOAST_HEIGHT);
+                    ui.render_widget(Clear, area);
+                    ui.render_widget(toast(notification, &state), area);
+                }
+            })?;
+        }
+
+        let event = tokio::select! {
+            event = events.next() => Some(event?),
+            // Something came back from the backend, draw it
+            _ = changed.notified() => None,
+        };
+
+        let mut state = shared_state.lock().expect("Could not lock state");
+        dirty = match event {
+            Some(Event::Input(i)) => {
+                if let Some(Action::Quit) =
+                    handle_input(i, &mut state, textarea, &mut command_textarea)
                 {
-                    return quit();
+                    return Ok(());
                 }
-                handle_input(i, &mut state, textarea, &mut command_textarea);
+                true
             }
-            Event::Tick => state.tick(),
-        }
+            // Nothing to click on while the terminal is too small
+            Some(Event::Mouse(mouse)) if fits => handle_mouse(
+                mouse,
+                &mut state,
+                &mut notes_view,
+                size,
+                &mut command_textarea,
+            ),
+            Some(Event::Mouse(_)) => false,
+            Some(Event::Paste(text)) => {
+                handle_paste(&text, &mut state, textarea, &mut command_textarea)
+            }
+            Some(Event::Resize(_, _)) => {
+                terminal.autoresize()?;
+                true
+            }
+            Some(Event::Tick) => state.tick(),
+            None => true,
+        };
     }
 }
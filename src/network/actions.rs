@@ -1,12 +1,108 @@
-use crate::app::note::Note;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::app::{
+    comment::Comment, note::Note, participant::Participant, reaction::Reaction,
+    sentiment::Sentiment,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Everything the UI asks of the backend. Only the changes to notes can be
+/// written to the offline journal, the other variants are skipped. Changes
+/// to existing notes carry only what the user changed, so replaying them
+/// later doesn't overwrite what others changed in the meantime. Journaled
+/// variants hold a single value, TOML loses the variant of any other kind.
 pub enum NetworkAction {
-    JoinRetro(String),
+    #[serde(skip)]
+    JoinRetro(Participant),
+    #[serde(skip)]
+    LeaveRetro(String),
+    #[serde(skip)]
+    Heartbeat(Participant),
+    #[serde(skip)]
+    GetParticipants,
     PublishNote(Note),
+    EditNote(NoteEdit),
+    DeleteNote(Note),
     Vote(Note),
     Unvote(Note),
-    Group(Note, Note),
+    React(ReactionChange),
+    Group(GroupChange),
+    UpdateActionItem(ActionItemChange),
+    MarkDone(DoneChange),
+    #[serde(skip)]
     GetNotes,
+    #[serde(skip)]
+    GetComments(String),
+    #[serde(skip)]
+    AddComment(Note, Comment),
+    #[serde(skip)]
+    GetTrends(String),
+    #[serde(skip)]
     ListenForChanges,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// New text of a note, with the sentiment worked out from it
+pub struct NoteEdit {
+    pub id: String,
+    pub text: String,
+    pub sentiment: Sentiment,
+    pub inferred: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Notes moved to the named group, or out of any group if None
+pub struct GroupChange {
+    pub ids: Vec<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The current user's reaction added to a note, or taken back
+pub struct ReactionChange {
+    pub id: String,
+    pub reaction: Reaction,
+    pub added: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A note turned into an action item that isn't done, or back into a plain
+/// note
+pub struct ActionItemChange {
+    pub id: String,
+    pub action: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An action item marked as done, or as not done
+pub struct DoneChange {
+    pub id: String,
+    pub done: bool,
+}
+
+impl NetworkAction {
+    /// What the action does, as in "could not {description}"
+    pub fn description(&self) -> &'static str {
+        match self {
+            NetworkAction::JoinRetro(_) => "join the retro",
+            NetworkAction::LeaveRetro(_) => "leave the retro",
+            NetworkAction::Heartbeat(_) => "tell the others you're here",
+            NetworkAction::GetParticipants => "load the participants",
+            NetworkAction::PublishNote(_) => "publish the note",
+            NetworkAction::EditNote(_) => "save the note",
+            NetworkAction::DeleteNote(_) => "delete the note",
+            NetworkAction::Vote(_) => "vote",
+            NetworkAction::Unvote(_) => "take the vote back",
+            NetworkAction::React(_) => "react",
+            NetworkAction::Group(_) => "group the notes",
+            NetworkAction::UpdateActionItem(_) | NetworkAction::MarkDone(_) => {
+                "update the action item"
+            }
+            NetworkAction::GetNotes => "load the notes",
+            NetworkAction::GetComments(_) => "load the comments",
+            NetworkAction::AddComment(_, _) => "add the comment",
+            NetworkAction::GetTrends(_) => "load the trends",
+            NetworkAction::ListenForChanges => "listen for changes",
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use anyhow::Result;
+use firestore_grpc::{
+    tonic::{transport::Channel, Code},
+    v1::{
+        firestore_client::FirestoreClient,
+        precondition::ConditionType,
+        run_query_request::QueryType,
+        structured_query::{
+            field_filter::Operator, filter::FilterType, CollectionSelector, FieldFilter,
+            FieldReference, Filter,
+        },
+        value::ValueType,
+        CreateDocumentRequest, DeleteDocumentRequest, Document, DocumentMask, GetDocumentRequest,
+        ListDocumentsRequest, Precondition, RunQueryRequest, StructuredQuery,
+        UpdateDocumentRequest, Value,
+    },
+};
+use futures::future::try_join_all;
+
+use crate::app::{note::Note, room::Room, stats::RoomStats};
+
+/// How many documents are fetched per request when listing a collection
+pub const PAGE_SIZE: i32 = 300;
+
+/// How many rooms `retro list` shows
+const ROOMS_LISTED: i32 = 20;
+
+/// Collections stored under a room, removed together with the room
+const ROOM_COLLECTIONS: [&str; 2] = ["notes", "participants"];
+
+/// Where the retros are stored. Everything that talks to the database goes
+/// through here, both the room management commands and the live [`Remote`].
+///
+/// [`Remote`]: super::remote::Remote
+#[derive(Clone, Debug)]
+pub struct Backend {
+    project_id: String,
+
+    endpoint: String,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::new(None, None)
+    }
+}
+
+impl Backend {
+    /// Create a backend, falling back to the shared retro project for any
+    /// setting that isn't given.
+    pub fn new(project_id: Option<String>, endpoint: Option<String>) -> Self {
+        Backend {
+            project_id: project_id.unwrap_or_else(|| "retrodog-23512".to_string()),
+            endpoint: endpoint.unwrap_or_else(|| "https://firestore.googleapis.com".to_string()),
+        }
+    }
+
+    /// Open a new connection to the database
+    pub async fn connect(&self) -> Result<FirestoreClient<Channel>> {
+        Ok(FirestoreClient::connect(self.endpoint.clone()).await?)
+    }
+
+    /// Resource name of the database
+    pub fn database(&self) -> String {
+        format!("projects/{}/databases/(default)", self.project_id)
+    }
+
+    /// Resource name of a room document
+    pub fn room_path(&self, room_id: &str) -> String {
+        format!("{}/documents/retros/{room_id}", self.database())
+    }
+
+    /// Store a new room, returning the id others can use to join it
+    pub async fn create_room(&self, room: &Room) -> Result<String> {
+        let mut client = self.connect().await?;
+
+        let document = client
+            .create_document(CreateDocumentRequest {
+                parent: format!("{}/documents", self.database()),
+                collection_id: "retros".into(),
+                document_id: "".into(),
+                document: Some(Document {
+                    name: "".into(),
+                    fields: room.into(),
+                    create_time: None,
+                    update_time: None,
+                }),
+                mask: None,
+            })
+            .await?
+            .into_inner();
+
+        Ok(Self::document_id(&document.name))
+    }
+
+    /// Fetch a room, rooms that were never created through `retro create`
+    /// don't have any settings and yield None.
+    pub async fn get_room(&self, room_id: &str) -> Result<Option<Room>> {
+        let mut client = self.connect().await?;
+
+        let res = client
+            .get_document(GetDocumentRequest {
+                name: self.room_path(room_id),
+                mask: None,
+                consistency_selector: None,
+            })
+            .await;
+
+        match res {
+            Ok(document) => {
+                let document = document.into_inner();
+                let mut room: Room = document.fields.into();
+                room.id = Self::document_id(&document.name);
+                Ok(Some(room))
+            }
+            Err(status) if status.code() == Code::NotFound => Ok(None),
+            Err(status) => Err(status.into()),
+        }
+    }
+
+    /// The most recently created rooms, newest first. Pages are fetched
+    /// until there are enough rooms that aren't filtered out.
+    pub async fn list_rooms(&self, include_archived: bool) -> Result<Vec<Room>> {
+        let mut client = self.connect().await?;
+
+        let mut request = ListDocumentsRequest {
+            parent: format!("{}/documents", self.database()),
+            collection_id: "retros".to_string(),
+            page_size: ROOMS_LISTED,
+            page_token: "".to_string(),
+            order_by: "created_at desc".to_string(),
+            mask: None,
+            show_missing: false,
+            consistency_selector: None,
+        };
+        let mut rooms = vec![];
+
+        loop {
+            let page = client.list_documents(request.clone()).await?.into_inner();
+            rooms.extend(
+                page.documents
+                    .into_iter()
+                    .map(|document| {
+                        let mut room: Room = document.fields.into();
+                        room.id = Self::document_id(&document.name);
+                        room
+                    })
+                    .filter(|room| include_archived || !room.archived),
+            );
+
+            if rooms.len() >= ROOMS_LISTED as usize || page.next_page_token.is_empty() {
+                rooms.truncate(ROOMS_LISTED as usize);
+                return Ok(rooms);
+            }
+            request.page_token = page.next_page_token;
+        }
+    }
+
+    /// Stats of every room in the series, archived or not, oldest first. The
+    /// notes of all the rooms are fetched at the same time.
+    pub async fn series_stats(&self, series: &str) -> Result<Vec<RoomStats>> {
+        let mut client = self.connect().await?;
+
+        let mut rooms = self.series_rooms(&mut client, series).await?;
+        // Ordering on the server would need an index on series and created_at
+        rooms.sort_by_key(|room| room.created_at);
+
+        try_join_all(rooms.iter().map(|room| {
+            let mut client = client.clone();
+            async move {
+                let notes: Vec<Note> = Self::list_all(
+                    &mut client,
+                    ListDocumentsRequest {
+                        parent: self.room_path(&room.id),
+                        collection_id: "notes".to_string(),
+                        page_size: PAGE_SIZE,
+                        page_token: "".to_string(),
+                        order_by: "".to_string(),
+                        mask: None,
+                        show_missing: false,
+                        consistency_selector: None,
+                    },
+                    |_| {},
+                )
+                .await?
+                .into_iter()
+                .map(Note::from)
+                .collect();
+
+                Ok(RoomStats::new(room, &notes))
+            }
+        }))
+        .await
+    }
+
+    /// The rooms of the series, in no particular order
+    async fn series_rooms(
+        &self,
+        client: &mut FirestoreClient<Channel>,
+        series: &str,
+    ) -> Result<Vec<Room>> {
+        let mut responses = client
+            .run_query(RunQueryRequest {
+                parent: format!("{}/documents", self.database()),
+                query_type: Some(QueryType::StructuredQuery(StructuredQuery {
+                    select: None,
+                    from: vec![CollectionSelector {
+                        collection_id: "retros".to_string(),
+                        all_descendants: false,
+                    }],
+                    r#where: Some(Filter {
+                        filter_type: Some(FilterType::FieldFilter(FieldFilter {
+                            field: Some(FieldReference {
+                                field_path: "series".to_string(),
+                            }),
+                            op: Operator::Equal as i32,
+                            value: Some(Value {
+                                value_type: Some(ValueType::StringValue(series.to_string())),
+                            }),
+                        })),
+                    }),
+                    order_by: vec![],
+                    start_at: None,
+                    end_at: None,
+                    offset: 0,
+                    limit: None,
+                })),
+                consistency_selector: None,
+            })
+            .await?
+            .into_inner();
+
+        let mut rooms = vec![];
+        while let Some(response) = responses.message().await? {
+            // Responses without a document only report progress
+            if let Some(document) = response.document {
+                let mut room: Room = document.fields.into();
+                room.id = Self::document_id(&document.name);
+                rooms.push(room);
+            }
+        }
+
+        Ok(rooms)
+    }
+
+    /// Mark a room as archived, it's kept around but can no longer be joined
+    pub async fn archive_room(&self, room_id: &str) -> Result<()> {
+        let mut client = self.connect().await?;
+
+        let mut archived = Room::new("".into(), Default::default(), None, None, None);
+        archived.archived = true;
+
+        client
+            .update_document(UpdateDocumentRequest {
+                document: Some(Document {
+                    name: self.room_path(room_id),
+                    fields: (&archived).into(),
+                    create_time: None,
+                    update_time: None,
+                }),
+                update_mask: Some(DocumentMask {
+                    field_paths: vec!["archived".to_string()],
+                }),
+                mask: None,
+                current_document: Some(Precondition {
+                    condition_type: Some(ConditionType::Exists(true)),
+                }),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete a room together with all of its notes, comments and participants
+    pub async fn delete_room(&self, room_id: &str) -> Result<()> {
+        let mut client = self.connect().await?;
+        let room = self.room_path(room_id);
+
+        for note in Self::document_names(&mut client, &room, "notes").await? {
+            Self::delete_collection(&mut client, &note, "comments").await?;
+        }
+
+        for collection in ROOM_COLLECTIONS {
+            Self::delete_collection(&mut client, &room, collection).await?;
+        }
+
+        client
+            .delete_document(DeleteDocumentRequest {
+                name: room,
+                current_document: None,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete a note together with its comments, `note_id` is the full
+    /// resource name of the note
+    pub async fn delete_note(&self, note_id: &str) -> Result<()> {
+        let mut client = self.connect().await?;
+
+        Self::delete_collection(&mut client, note_id, "comments").await?;
+        client
+            .delete_document(DeleteDocumentRequest {
+                name: note_id.to_string(),
+                current_document: None,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Every document the request lists, following the page tokens until the
+    /// last page. `progress` is told how many documents were fetched so far
+    /// after every page.
+    pub async fn list_all(
+        client: &mut FirestoreClient<Channel>,
+        mut request: ListDocumentsRequest,
+        mut progress: impl FnMut(usize),
+    ) -> Result<Vec<Document>> {
+        let mut documents = vec![];
+
+        loop {
+            let page = client.list_documents(request.clone()).await?.into_inner();
+            documents.extend(page.documents);
+            progress(documents.len());
+
+            if page.next_page_token.is_empty() {
+                return Ok(documents);
+            }
+            request.page_token = page.next_page_token;
+        }
+    }
+
+    async fn document_names(
+        client: &mut FirestoreClient<Channel>,
+        parent: &str,
+        collection: &str,
+    ) -> Result<Vec<String>> {
+        let documents = Self::list_all(
+            client,
+            ListDocumentsRequest {
+                parent: parent.to_string(),
+                collection_id: collection.to_string(),
+                page_size: PAGE_SIZE,
+                page_token: "".to_string(),
+                order_by: "".to_string(),
+                mask: Some(DocumentMask {
+                    field_paths: vec![],
+                }),
+                show_missing: false,
+                consistency_selector: None,
+            },
+            |_| {},
+        )
+        .await?;
+
+        Ok(documents
+            .into_iter()
+            .map(|document| document.name)
+            .collect())
+    }
+
+    async fn delete_collection(
+        client: &mut FirestoreClient<Channel>,
+        parent: &str,
+        collection: &str,
+    ) -> Result<()> {
+        for name in Self::document_names(client, parent, collection).await? {
+            client
+                .delete_document(DeleteDocumentRequest {
+                    name,
+                    current_document: None,
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn document_id(name: &str) -> String {
+        name.rsplit('/').next().unwrap_or_default().to_string()
+    }
+}
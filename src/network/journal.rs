@@ -0,0 +1,233 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use firestore_grpc::tonic::{transport, Code, Status};
+use serde::{Deserialize, Serialize};
+
+use super::actions::NetworkAction;
+
+#[derive(Clone, Debug, Default)]
+/// Changes that couldn't be sent because the connection was down, oldest
+/// first. They're kept in a file so they survive a restart, and replayed in
+/// order once the backend can be reached again.
+pub struct Journal {
+    /// Where the journal is stored, only kept in memory if None
+    path: Option<PathBuf>,
+
+    actions: Vec<NetworkAction>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct JournalFile {
+    actions: Vec<NetworkAction>,
+}
+
+impl Journal {
+    /// Default location of the journal of a room, in the platform's local
+    /// data dir. Room ids that would point anywhere else are rejected.
+    pub fn default_path(room_id: &str) -> Result<Option<PathBuf>> {
+        if room_id.is_empty() || room_id.contains(['/', '\\']) || room_id.contains("..") {
+            bail!("invalid room id `{room_id}`");
+        }
+
+        Ok(dirs::data_local_dir().map(|dir| {
+            dir.join("retro")
+                .join("journal")
+                .join(format!("{room_id}.toml"))
+        }))
+    }
+
+    /// Read the journal from the given path, a missing file yields an empty
+    /// journal
+    pub fn open(path: &Path) -> Result<Self> {
+        let actions = match fs::read_to_string(path) {
+            Ok(contents) => {
+                let file: JournalFile = toml::from_str(&contents)
+                    .with_context(|| format!("could not parse {}", path.display()))?;
+                file.actions
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+        };
+
+        Ok(Journal {
+            path: Some(path.to_path_buf()),
+            actions,
+        })
+    }
+
+    /// If the action is a change that can safely be sent again. Reads and
+    /// presence updates are stale by the time the connection is back, and
+    /// comments would be counted twice.
+    pub fn accepts(action: &NetworkAction) -> bool {
+        matches!(
+            action,
+            NetworkAction::PublishNote(_)
+                | NetworkAction::EditNote(_)
+                | NetworkAction::DeleteNote(_)
+                | NetworkAction::Vote(_)
+                | NetworkAction::Unvote(_)
+                | NetworkAction::React(_)
+                | NetworkAction::Group(_)
+                | NetworkAction::UpdateActionItem(_)
+                | NetworkAction::MarkDone(_)
+        )
+    }
+
+    /// Add an action to the end of the journal
+    pub fn push(&mut self, action: NetworkAction) -> Result<()> {
+        self.actions.push(action);
+        self.save()
+    }
+
+    /// The oldest action, the next one to replay
+    pub fn first(&self) -> Option<&NetworkAction> {
+        self.actions.first()
+    }
+
+    /// Drop the oldest action, once it has been replayed
+    pub fn pop_front(&mut self) -> Result<()> {
+        if !self.actions.is_empty() {
+            self.actions.remove(0);
+        }
+        self.save()
+    }
+
+    /// Every action waiting to be replayed, oldest first
+    pub fn actions(&self) -> &[NetworkAction] {
+        &self.actions
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if self.actions.is_empty() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    Err(e).with_context(|| format!("could not remove {}", path.display()))
+                }
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("could not create {}", dir.display()))?;
+        }
+        let contents = toml::to_string(&JournalFile {
+            actions: self.actions.clone(),
+        })?;
+        fs::write(path, contents).with_context(|| format!("could not write {}", path.display()))
+    }
+}
+
+/// If the request failed because the backend couldn't be reached, rather than
+/// because it rejected the request
+pub fn is_offline(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(status) = cause.downcast_ref::<Status>() {
+            return matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded);
+        }
+        cause.downcast_ref::<transport::Error>().is_some()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{lexicon::Lexicon, note::Note, reaction::Reaction},
+        network::actions::{GroupChange, ReactionChange},
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("retro-{name}-{}.toml", std::process::id()))
+    }
+
+    #[test]
+    fn actions_survive_a_restart() {
+        let path = temp_path("journal-round-trip");
+        let note = Note::new(
+            "ann".to_string(),
+            "hello :)".to_string(),
+            &Lexicon::default(),
+        );
+
+        let mut journal = Journal::open(&path).unwrap();
+        journal
+            .push(NetworkAction::PublishNote(note.clone()))
+            .unwrap();
+        journal
+            .push(NetworkAction::Group(GroupChange {
+                ids: vec![note.id.clone()],
+                name: None,
+            }))
+            .unwrap();
+        journal
+            .push(NetworkAction::React(ReactionChange {
+                id: note.id.clone(),
+                reaction: Reaction::Tada,
+                added: true,
+            }))
+            .unwrap();
+
+        let reopened = Journal::open(&path).unwrap();
+        assert_eq!(
+            format!("{:?}", reopened.actions()),
+            format!("{:?}", journal.actions())
+        );
+
+        // The file goes away with the last action
+        while !journal.is_empty() {
+            journal.pop_front().unwrap();
+        }
+        assert!(!path.exists());
+        assert!(Journal::open(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn room_ids_stay_in_the_journal_dir() {
+        for room_id in ["", "../config", "a/b", "a\\b", ".."] {
+            assert!(Journal::default_path(room_id).is_err(), "{room_id}");
+        }
+        assert!(Journal::default_path("x7Gq2").is_ok());
+    }
+
+    #[test]
+    fn reads_are_not_journaled() {
+        assert!(!Journal::accepts(&NetworkAction::GetNotes));
+        assert!(!Journal::accepts(&NetworkAction::GetTrends(
+            "sprints".to_string()
+        )));
+    }
+
+    #[test]
+    fn unreachable_backends_are_offline() {
+        assert!(is_offline(&Status::unavailable("down").into()));
+        assert!(is_offline(&Status::deadline_exceeded("slow").into()));
+        assert!(is_offline(
+            &anyhow::Error::from(Status::unavailable("down")).context("could not vote")
+        ));
+    }
+
+    #[test]
+    fn rejected_requests_are_not_offline() {
+        assert!(!is_offline(&Status::not_found("gone").into()));
+        assert!(!is_offline(&Status::failed_precondition("changed").into()));
+        assert!(!is_offline(&anyhow::anyhow!("something else")));
+    }
+}
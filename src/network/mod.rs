@@ -1,2 +1,4 @@
 pub mod actions;
+pub mod backend;
+pub mod journal;
 pub mod remote;
@@ -1,30 +1,50 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     sync::{Arc, Mutex},
 };
 
-use crate::app::{note::Note, state::State};
+use crate::app::{
+    comment::Comment,
+    note::{document_id, Note},
+    notification::Level,
+    participant::Participant,
+    reaction::Reaction,
+    state::State,
+};
 
-use super::actions::NetworkAction;
+use super::{
+    actions::NetworkAction,
+    backend::{Backend, PAGE_SIZE},
+    journal::{is_offline, Journal},
+};
 use anyhow::Result;
+use chrono::Utc;
 
 use firestore_grpc::{
-    tonic::{metadata::MetadataValue, transport::Channel, Request},
+    tonic::{metadata::MetadataValue, transport::Channel, Code, Request},
     v1::{
+        document_transform::{field_transform::TransformType, FieldTransform},
         firestore_client::FirestoreClient,
         listen_request::TargetChange,
-        listen_response::ResponseType,
+        precondition::ConditionType,
         target::{DocumentsTarget, TargetType},
-        CreateDocumentRequest, Document, ListDocumentsRequest, ListenRequest, ListenResponse,
-        Target, UpdateDocumentRequest,
+        value::ValueType,
+        write::Operation,
+        ArrayValue, CommitRequest, CreateDocumentRequest, DeleteDocumentRequest, Document,
+        DocumentMask, DocumentTransform, GetDocumentRequest, ListDocumentsRequest, ListenRequest,
+        Precondition, Target, UpdateDocumentRequest, Value, Write,
     },
 };
 
 use futures::{stream, StreamExt};
 
+/// How many times a change is tried again when the note was changed by
+/// someone else between reading and writing it
+const MAX_RETRIES: usize = 5;
+
 #[derive(Clone, Debug)]
 pub struct Remote<'a> {
-    project_id: &'a str,
+    backend: Backend,
 
     room_id: &'a String,
 
@@ -37,32 +57,208 @@ enum VoteDirection {
 }
 
 impl<'a> Remote<'a> {
-    pub fn new(room_id: &'a String, state: &'a Arc<Mutex<State>>) -> Self {
-        let project_id = "retrodog-23512";
-
+    pub fn new(backend: Backend, room_id: &'a String, state: &'a Arc<Mutex<State>>) -> Self {
         Remote {
-            project_id,
+            backend,
             room_id,
             state,
         }
     }
 
+    /// Send the action to the backend. Changes that can't be sent while the
+    /// connection is down go to the journal, any other failure is undone and
+    /// shown to the user.
+    pub async fn run(&self, action: NetworkAction) {
+        // Changes queue up behind the journaled ones, so they're applied in
+        // the order they were made
+        if Journal::accepts(&action) && !self.state.lock().expect("oh no").journal.is_empty() {
+            self.journal(action);
+            self.replay().await;
+            return;
+        }
+
+        match self.handle_event(action.clone()).await {
+            // Whatever failed before might go through now
+            Ok(()) => {
+                self.set_online(true);
+                self.replay().await;
+            }
+            Err(e) if is_offline(&e) => {
+                self.set_online(false);
+
+                // Reads are done again once the connection is back
+                if Journal::accepts(&action) {
+                    self.journal(action);
+                } else if let NetworkAction::AddComment(_, _) = action {
+                    self.reject(&action, &e);
+                }
+            }
+            Err(e) => self.reject(&action, &e),
+        }
+    }
+
+    /// Tell the user when the connection drops or comes back, the notes are
+    /// loaded again once it's back
+    fn set_online(&self, online: bool) {
+        let mut state = self.state.lock().expect("oh no");
+        if state.offline != online {
+            return;
+        }
+        state.offline = !online;
+
+        if online {
+            state.notify(Level::Info, "back online");
+            state.dispatch(NetworkAction::GetNotes);
+        } else {
+            state.notify(
+                Level::Warning,
+                "offline, changes are kept and synced once the connection is back",
+            );
+        }
+    }
+
+    /// Send the journaled changes, oldest first, until the journal is empty
+    /// or the connection drops again
+    async fn replay(&self) {
+        loop {
+            let Some(action) = self.state.lock().expect("oh no").journal.first().cloned() else {
+                return;
+            };
+
+            let result = self.handle_event(action.clone()).await;
+            match &result {
+                Err(e) if is_offline(e) => return,
+                Err(e) => self.reject(&action, e),
+                Ok(()) => {}
+            }
+
+            let mut state = self.state.lock().expect("oh no");
+            if let Err(e) = state.journal.pop_front() {
+                state.notify(Level::Error, format!("{e:#}"));
+            }
+            if state.journal.is_empty() {
+                state.notify(Level::Info, "all changes are synced");
+            }
+        }
+    }
+
+    fn journal(&self, action: NetworkAction) {
+        let mut state = self.state.lock().expect("oh no");
+
+        if let Err(e) = state.journal.push(action.clone()) {
+            state.roll_back(&action);
+            state.notify(Level::Error, format!("{e:#}"));
+        }
+    }
+
+    /// Undo the local changes of a failed action and tell the user
+    fn reject(&self, action: &NetworkAction, error: &anyhow::Error) {
+        let mut state = self.state.lock().expect("oh no");
+        state.roll_back(action);
+        state.notify(
+            Level::Error,
+            format!("could not {}: {error:#}", action.description()),
+        );
+    }
+
     pub async fn handle_event(&self, action: NetworkAction) -> Result<()> {
         match action {
-            NetworkAction::JoinRetro(room_id) => println!("join {}", room_id),
+            NetworkAction::JoinRetro(participant) => {
+                self.announce(&participant).await?;
+                self.get_participants().await?;
+            }
+            NetworkAction::LeaveRetro(session_id) => {
+                self.leave(&session_id).await?;
+            }
+            NetworkAction::Heartbeat(participant) => {
+                self.announce(&participant).await?;
+            }
+            NetworkAction::GetParticipants => {
+                self.get_participants().await?;
+            }
             NetworkAction::PublishNote(note) => {
-                self.create_note(&note).await?;
+                let created = self.create_note(&note).await?;
+                self.state
+                    .lock()
+                    .expect("oh no")
+                    .confirm_note(&note.id, created);
+            }
+            NetworkAction::EditNote(edit) => {
+                let fields = HashMap::from([
+                    ("text".to_string(), string_value(edit.text)),
+                    ("sentiment".to_string(), string_value(edit.sentiment.into())),
+                    ("inferred".to_string(), bool_value(edit.inferred)),
+                ]);
+                self.update_note(&edit.id, fields).await?;
+                self.get_notes().await?;
+            }
+            NetworkAction::DeleteNote(note) => {
+                self.backend.delete_note(&note.id).await?;
+                self.get_notes().await?;
             }
             NetworkAction::Vote(note) => {
-                self.vote(&note, VoteDirection::Up).await?;
+                let voted = self.vote(&note, VoteDirection::Up).await?;
+                self.state
+                    .lock()
+                    .expect("oh no")
+                    .confirm_note(&note.id, voted);
             }
             NetworkAction::Unvote(note) => {
-                self.vote(&note, VoteDirection::Down).await?;
+                let unvoted = self.vote(&note, VoteDirection::Down).await?;
+                self.state
+                    .lock()
+                    .expect("oh no")
+                    .confirm_note(&note.id, unvoted);
+            }
+            NetworkAction::React(change) => {
+                self.react(&change.id, change.reaction, change.added)
+                    .await?;
+                self.get_notes().await?;
+            }
+            NetworkAction::Group(change) => {
+                self.group(&change.ids, change.name).await?;
+                self.get_notes().await?;
+            }
+            NetworkAction::UpdateActionItem(change) => {
+                let fields = HashMap::from([
+                    ("action".to_string(), bool_value(change.action)),
+                    ("done".to_string(), bool_value(false)),
+                ]);
+                self.update_note(&change.id, fields).await?;
+                self.get_notes().await?;
+            }
+            NetworkAction::MarkDone(change) => {
+                let fields = HashMap::from([("done".to_string(), bool_value(change.done))]);
+                self.update_note(&change.id, fields).await?;
+                self.get_notes().await?;
             }
-            NetworkAction::Group(id1, id2) => println!("group {}{}", id1, id2),
             NetworkAction::GetNotes => {
                 self.get_notes().await?;
             }
+            NetworkAction::GetComments(note_id) => {
+                self.get_comments(&note_id).await?;
+            }
+            NetworkAction::AddComment(note, comment) => {
+                self.add_comment(&note, &comment).await?;
+                self.get_comments(&note.id).await?;
+                self.get_notes().await?;
+            }
+            NetworkAction::GetTrends(series) => {
+                // Loading every retro of the series takes a while, the
+                // other actions don't wait for it
+                let backend = self.backend.clone();
+                let state = Arc::clone(self.state);
+                tokio::spawn(async move {
+                    let trends = backend.series_stats(&series).await;
+                    let mut state = state.lock().expect("oh no");
+                    match trends {
+                        Ok(trends) => state.set_trends(trends),
+                        Err(e) => {
+                            state.notify(Level::Error, format!("could not load the trends: {e:#}"))
+                        }
+                    }
+                });
+            }
             NetworkAction::ListenForChanges => {
                 self.listen_for_changes().await?;
             }
@@ -70,14 +266,16 @@ impl<'a> Remote<'a> {
         Ok(())
     }
 
-    async fn create_note(&self, note: &'a Note) -> Result<&Note> {
+    /// Store a new note under its client side id, returns it as the backend
+    /// saved it. Publishing the same note twice stores it once.
+    async fn create_note(&self, note: &Note) -> Result<Note> {
         let (root, mut client, _) = self.get_client().await?;
 
-        client
+        let res = client
             .create_document(CreateDocumentRequest {
                 parent: root.to_string(),
                 collection_id: "notes".into(),
-                document_id: "".into(),
+                document_id: note.id.clone(),
                 document: Some(firestore_grpc::v1::Document {
                     name: "".into(),
                     fields: note.into(),
@@ -86,10 +284,38 @@ impl<'a> Remote<'a> {
                 }),
                 mask: None,
             })
-            .await?;
+            .await;
+
+        let document = match res {
+            Ok(document) => document.into_inner(),
+            // An earlier attempt got through, even if it looked like it failed
+            Err(status) if status.code() == Code::AlreadyExists => {
+                self.get_note(&mut client, &format!("{root}/notes/{}", note.id))
+                    .await?
+            }
+            Err(status) => return Err(status.into()),
+        };
+
+        Ok(document.into())
+    }
+
+    async fn get_note(
+        &self,
+        client: &mut FirestoreClient<Channel>,
+        name: &str,
+    ) -> Result<Document> {
+        let document = client
+            .get_document(GetDocumentRequest {
+                name: name.to_string(),
+                mask: None,
+                consistency_selector: None,
+            })
+            .await?
+            .into_inner();
 
-        Ok(note)
+        Ok(document)
     }
+
     async fn listen_for_changes(&self) -> Result<()> {
         let (room, mut client, db) = self.get_client().await?;
 
@@ -116,15 +342,10 @@ impl<'a> Remote<'a> {
         let mut res = client.listen(req).await?.into_inner();
 
         while let Some(msg) = res.next().await {
-            if let Ok(msg) = msg {
-                if let ListenResponse {
-                    response_type: Some(ResponseType::DocumentChange(document)),
-                } = msg
-                {
-                    println!("{:?}", document)
-                }
+            if msg.is_ok() {
                 let mut state = self.state.lock().expect("oh no");
                 state.dispatch(NetworkAction::GetNotes);
+                state.dispatch(NetworkAction::GetParticipants);
                 // state.dispatch(NetworkAction::ListenForChanges);
                 break;
             }
@@ -136,69 +357,337 @@ impl<'a> Remote<'a> {
     async fn get_notes(&self) -> Result<()> {
         let (root, mut client, _db) = self.get_client().await?;
 
-        let res = client
-            .list_documents(ListDocumentsRequest {
+        let documents = Backend::list_all(
+            &mut client,
+            ListDocumentsRequest {
                 parent: root,
                 collection_id: "notes".to_string(),
-                page_size: 1000,
+                page_size: PAGE_SIZE,
+                page_token: "".to_string(),
+                order_by: "".to_string(),
+                mask: None,
+                show_missing: false,
+                consistency_selector: None,
+            },
+            |loaded| {
+                let mut state = self.state.lock().expect("oh no");
+                state.loading_notes = Some(loaded);
+                state.request_redraw();
+            },
+        )
+        .await;
+        self.state.lock().expect("oh no").loading_notes = None;
+
+        let notes: Vec<Note> = documents?.into_iter().map(Note::from).collect();
+
+        let mut state = self.state.lock().expect("oh no");
+        state.set_notes(notes);
+
+        Ok(())
+    }
+
+    async fn get_comments(&self, note_id: &str) -> Result<()> {
+        let (_root, mut client, _db) = self.get_client().await?;
+
+        let documents = Backend::list_all(
+            &mut client,
+            ListDocumentsRequest {
+                parent: note_id.to_string(),
+                collection_id: "comments".to_string(),
+                page_size: PAGE_SIZE,
                 page_token: "".to_string(),
-                order_by: "votes".to_string(),
+                order_by: "created_at".to_string(),
                 mask: None,
                 show_missing: false,
                 consistency_selector: None,
+            },
+            |_| {},
+        )
+        .await?;
+
+        let comments: Vec<Comment> = documents
+            .into_iter()
+            .map(|document| {
+                let mut comment: Comment = document.fields.into();
+                comment.id = document.name;
+                comment
+            })
+            .collect();
+
+        let mut state = self.state.lock().expect("oh no");
+        state.set_comments(note_id, comments);
+
+        Ok(())
+    }
+
+    async fn add_comment(&self, note: &Note, comment: &Comment) -> Result<()> {
+        let (_root, mut client, db) = self.get_client().await?;
+
+        client
+            .create_document(CreateDocumentRequest {
+                parent: note.id.clone(),
+                collection_id: "comments".into(),
+                document_id: "".into(),
+                document: Some(Document {
+                    name: "".into(),
+                    fields: comment.into(),
+                    create_time: None,
+                    update_time: None,
+                }),
+                mask: None,
+            })
+            .await?;
+
+        // Counted by the backend, others may be commenting at the same time
+        let increment = TransformType::Increment(Value {
+            value_type: Some(ValueType::IntegerValue(1)),
+        });
+        Self::transform_note(&mut client, db, &note.id, "comments", increment).await?;
+
+        Ok(())
+    }
+
+    /// Create or refresh the presence document for a participant
+    async fn announce(&self, participant: &Participant) -> Result<()> {
+        let (root, mut client, _) = self.get_client().await?;
+
+        client
+            .update_document(UpdateDocumentRequest {
+                document: Some(Document {
+                    name: Self::participant_path(&root, &participant.id),
+                    fields: participant.into(),
+                    create_time: None,
+                    update_time: None,
+                }),
+                update_mask: None,
+                mask: None,
+                current_document: None,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn leave(&self, session_id: &str) -> Result<()> {
+        let (root, mut client, _) = self.get_client().await?;
+
+        client
+            .delete_document(DeleteDocumentRequest {
+                name: Self::participant_path(&root, session_id),
+                current_document: None,
             })
             .await?;
 
-        let notes: Vec<Note> = res
-            .into_inner()
-            .documents
+        Ok(())
+    }
+
+    async fn get_participants(&self) -> Result<()> {
+        let (root, mut client, _db) = self.get_client().await?;
+
+        let documents = Backend::list_all(
+            &mut client,
+            ListDocumentsRequest {
+                parent: root,
+                collection_id: "participants".to_string(),
+                page_size: PAGE_SIZE,
+                page_token: "".to_string(),
+                order_by: "display_name".to_string(),
+                mask: None,
+                show_missing: false,
+                consistency_selector: None,
+            },
+            |_| {},
+        )
+        .await?;
+
+        let now = Utc::now().timestamp();
+        let participants: Vec<Participant> = documents
             .into_iter()
-            .rev()
-            .map(|note| {
-                let mut converted: Note = note.fields.into();
-                converted.id = note.name;
-                converted
+            .map(|document| {
+                let mut participant: Participant = document.fields.into();
+                participant.id = document_id(&document.name).to_string();
+                participant
             })
+            .filter(|participant| !participant.is_gone(now))
             .collect();
 
-        let participants = HashSet::from_iter(
-            notes
-                .iter()
-                .map(|note| &note.author)
-                .cloned()
-                .collect::<Vec<String>>(),
-        );
-
         let mut state = self.state.lock().expect("oh no");
-        state.set_notes(notes);
         state.set_participants(participants);
 
         Ok(())
     }
 
-    async fn vote(&self, note: &Note, direction: VoteDirection) -> Result<()> {
+    fn participant_path(root: &str, session_id: &str) -> String {
+        format!("{root}/participants/{session_id}")
+    }
+
+    /// Add or take back the current user's vote, returns the note as the
+    /// backend saved it. The vote is counted on the stored note rather than
+    /// the one that was sent, so sending it twice only counts it once. The
+    /// note is only written if nobody changed it since it was read, otherwise
+    /// the vote is counted again on the newer note.
+    async fn vote(&self, note: &Note, direction: VoteDirection) -> Result<Note> {
         let (_root, mut client, _) = self.get_client().await?;
+        let me = self.state.lock().expect("oh no").display_name.clone();
+
+        let mut retries = 0;
+        loop {
+            let document = self.get_note(&mut client, &note.id).await?;
+            let read_at = document.update_time.clone();
+
+            let mut stored: Note = document.into();
+            let changed = match direction {
+                VoteDirection::Up => stored.voters.insert(me.clone()),
+                VoteDirection::Down => stored.voters.remove(&me),
+            };
+            if !changed {
+                return Ok(stored);
+            }
+            stored.votes = match direction {
+                VoteDirection::Up => stored.votes.saturating_add(1),
+                VoteDirection::Down => stored.votes.saturating_sub(1),
+            };
+
+            let res = client
+                .update_document(UpdateDocumentRequest {
+                    document: Some(Document {
+                        name: note.id.clone(),
+                        fields: (&stored).into(),
+                        create_time: None,
+                        update_time: None,
+                    }),
+                    update_mask: Some(DocumentMask {
+                        field_paths: vec!["votes".to_string(), "voters".to_string()],
+                    }),
+                    mask: None,
+                    current_document: read_at.map(|time| Precondition {
+                        condition_type: Some(ConditionType::UpdateTime(time)),
+                    }),
+                })
+                .await;
+
+            match res {
+                Ok(document) => return Ok(document.into_inner().into()),
+                // Someone else voted in between
+                Err(status)
+                    if matches!(status.code(), Code::FailedPrecondition | Code::Aborted)
+                        && retries < MAX_RETRIES =>
+                {
+                    retries += 1;
+                }
+                Err(status) => return Err(status.into()),
+            }
+        }
+    }
 
-        let changed = &Note {
-            votes: if let VoteDirection::Up = direction {
-                note.votes.checked_add(1).unwrap()
-            } else {
-                note.votes.checked_sub(1).unwrap()
-            },
-            ..note.clone()
+    /// Add or take back the current user's reaction. Only the user's own
+    /// name is added to or removed from the reaction in a single write, so
+    /// reactions others add at the same time are kept.
+    async fn react(&self, note_id: &str, reaction: Reaction, added: bool) -> Result<()> {
+        let (_root, mut client, db) = self.get_client().await?;
+        let me = self.state.lock().expect("oh no").display_name.clone();
+
+        let users = ArrayValue {
+            values: vec![string_value(me)],
         };
+        let transform = if added {
+            TransformType::AppendMissingElements(users)
+        } else {
+            TransformType::RemoveAllFromArray(users)
+        };
+
+        let field = format!("reactions.{}", reaction.name());
+        Self::transform_note(&mut client, db, note_id, &field, transform).await?;
+
+        Ok(())
+    }
+
+    /// Set the given fields of a note and leave the others as they are. A
+    /// note deleted in the meantime isn't brought back.
+    async fn update_note(&self, note_id: &str, fields: HashMap<String, Value>) -> Result<()> {
+        let (_root, mut client, _) = self.get_client().await?;
+        let field_paths = fields.keys().cloned().collect();
 
         client
             .update_document(UpdateDocumentRequest {
                 document: Some(Document {
-                    name: note.id.clone(),
-                    fields: changed.into(),
+                    name: note_id.to_string(),
+                    fields,
                     create_time: None,
                     update_time: None,
                 }),
-                update_mask: None,
+                update_mask: Some(DocumentMask { field_paths }),
                 mask: None,
-                current_document: None,
+                current_document: Some(Precondition {
+                    condition_type: Some(ConditionType::Exists(true)),
+                }),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Put the notes in the named group, or take them out of their group if
+    /// None
+    async fn group(&self, note_ids: &[String], name: Option<String>) -> Result<()> {
+        let (_root, mut client, _) = self.get_client().await?;
+
+        // Masked but missing from the fields, so it's removed
+        let fields = match name {
+            Some(name) => HashMap::from([("group".to_string(), string_value(name))]),
+            None => HashMap::new(),
+        };
+
+        for note_id in note_ids {
+            client
+                .update_document(UpdateDocumentRequest {
+                    document: Some(Document {
+                        name: note_id.clone(),
+                        fields: fields.clone(),
+                        create_time: None,
+                        update_time: None,
+                    }),
+                    update_mask: Some(DocumentMask {
+                        field_paths: vec!["group".to_string()],
+                    }),
+                    mask: None,
+                    current_document: Some(Precondition {
+                        condition_type: Some(ConditionType::Exists(true)),
+                    }),
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Change a field of a note on the backend, in a single write that
+    /// doesn't depend on what the client last read
+    async fn transform_note(
+        client: &mut FirestoreClient<Channel>,
+        database: String,
+        note_id: &str,
+        field_path: &str,
+        transform: TransformType,
+    ) -> Result<()> {
+        client
+            .commit(CommitRequest {
+                database,
+                writes: vec![Write {
+                    update_mask: None,
+                    update_transforms: vec![],
+                    current_document: Some(Precondition {
+                        condition_type: Some(ConditionType::Exists(true)),
+                    }),
+                    operation: Some(Operation::Transform(DocumentTransform {
+                        document: note_id.to_string(),
+                        field_transforms: vec![FieldTransform {
+                            field_path: field_path.to_string(),
+                            transform_type: Some(transform),
+                        }],
+                    })),
+                }],
+                transaction: vec![],
             })
             .await?;
 
@@ -206,11 +695,23 @@ impl<'a> Remote<'a> {
     }
 
     async fn get_client(&self) -> Result<(String, FirestoreClient<Channel>, String)> {
-        let db = format!("projects/{}/databases/(default)", self.project_id);
-        let room = format!("{db}/documents/retros/{}", self.room_id);
+        let db = self.backend.database();
+        let room = self.backend.room_path(self.room_id);
 
-        let service = FirestoreClient::connect("https://firestore.googleapis.com").await?;
+        let service = self.backend.connect().await?;
 
         Ok((room, service, db))
     }
 }
+
+fn string_value(value: String) -> Value {
+    Value {
+        value_type: Some(ValueType::StringValue(value)),
+    }
+}
+
+fn bool_value(value: bool) -> Value {
+    Value {
+        value_type: Some(ValueType::BooleanValue(value)),
+    }
+}
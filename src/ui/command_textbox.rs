@@ -1,18 +1,35 @@
 use tui::{
-    style::{Color, Style},
+    style::Style,
     widgets::{Block, Borders},
 };
 use tui_textarea::TextArea;
 
-pub fn command_textbox() -> TextArea<'static> {
+use crate::app::{state::State, theme::Theme};
+
+pub fn command_textbox(theme: &Theme) -> TextArea<'static> {
     let mut textarea = TextArea::default();
 
     textarea.set_block(
         Block::default()
             .borders(Borders::ALL)
             .title("Command")
-            .style(Style::default().bg(Color::Reset).fg(Color::LightRed)),
+            .style(Style::default().bg(theme.command_bg).fg(theme.command)),
     );
 
     textarea
 }
+
+/// The frame around the command box, tells how to review suggested groups
+/// while there are any
+pub fn command_block(state: &State) -> Block<'static> {
+    let title = match state.suggestions.len() {
+        0 => "Command".to_string(),
+        left => format!("Suggested group, {left} left (↵ accept, esc reject)"),
+    };
+
+    Block::default().borders(Borders::ALL).title(title).style(
+        Style::default()
+            .bg(state.theme.command_bg)
+            .fg(state.theme.command),
+    )
+}
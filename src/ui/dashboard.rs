@@ -0,0 +1,87 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    widgets::{BarChart, Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{sentiment::Sentiment, state::State};
+
+/// Width of a bar, names longer than this are cut off below the bar
+const BAR_WIDTH: u16 = 6;
+
+/// Draw the dashboard: how the notes feel, the notes with the most votes and
+/// who voted and wrote how much. Who voted is left out in anonymous rooms.
+/// Everything is computed from the notes on every draw, so it's always up
+/// to date.
+pub fn dashboard<B: Backend>(ui: &mut Frame<'_, B>, area: Rect, state: &State) {
+    let block = Block::default().borders(Borders::all()).title("Dashboard");
+    let inner = block.inner(area);
+    ui.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(8),
+            Constraint::Length(5),
+            Constraint::Min(0),
+        ])
+        .split(inner);
+    // Who voted how much would give away the votes of anonymous rooms
+    let charts = if state.anonymous {
+        vec![Rect::default(), rows[2]]
+    } else {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(rows[2])
+    };
+
+    let sentiments: Vec<(String, u64)> = state
+        .sentiment_count()
+        .iter()
+        .map(|(sentiment, count)| (sentiment.to_string(), *count as u64))
+        .collect();
+    ui.render_widget(bar_chart("Sentiment", &labels(&sentiments), state), rows[0]);
+
+    let top: Vec<String> = [Sentiment::Happy, Sentiment::Sad, Sentiment::Neutral]
+        .into_iter()
+        .map(|sentiment| match state.top_note(sentiment) {
+            Some(note) => format!("{sentiment} [+{}] {}", note.votes, note.text),
+            None => format!("{sentiment} -"),
+        })
+        .collect();
+    ui.render_widget(
+        Paragraph::new(top.join("\n"))
+            .block(Block::default().borders(Borders::TOP).title("Top voted")),
+        rows[1],
+    );
+
+    if !state.anonymous {
+        let votes = state.votes_per_participant();
+        ui.render_widget(bar_chart("Votes cast", &labels(&votes), state), charts[0]);
+    }
+
+    let notes = state.notes_per_author();
+    ui.render_widget(
+        bar_chart("Notes written", &labels(&notes), state),
+        charts[1],
+    );
+}
+
+fn labels(counts: &[(String, u64)]) -> Vec<(&str, u64)> {
+    counts
+        .iter()
+        .map(|(label, count)| (label.as_str(), *count))
+        .collect()
+}
+
+fn bar_chart<'a>(title: &'a str, data: &'a [(&'a str, u64)], state: &State) -> BarChart<'a> {
+    BarChart::default()
+        .block(Block::default().borders(Borders::TOP).title(title))
+        .data(data)
+        .bar_width(BAR_WIDTH)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(state.theme.chart))
+}
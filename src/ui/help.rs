@@ -1,47 +1,35 @@
-use tui::{
-    style::{Color, Style},
-    widgets::{Block, Borders, Paragraph},
-};
+use tui::widgets::{Block, Borders, Paragraph};
 
-use crate::app::{mode::Mode, state::State};
+use crate::app::{command::Command, mode::Mode, state::State};
 
-pub fn help(state: &State) -> Paragraph<'static> {
-    let shortcuts: &'static str = match state.mode {
-        Mode::Normal => {
-            r#"
-?  Show/hide help
-________________
-:  command mode
-________________
-e  export to csv
-q  quit retro
-"#
-        }
-        Mode::Insert => {
-            r#"
- ?   Show/hide help
-ESC  Normal mode
-________________
-↵    Create note
-"#
-        }
-        Mode::Command => {
-            r#"
-ESC  Normal mode
-________________
- g   1 2 .. n
- u   1 2 .. n upvote
- d   1 2 .. n downvote
- f   query
-"#
-        }
-    };
+/// The shortcuts for the active mode, generated from the keymap
+pub fn shortcuts(state: &State) -> Vec<String> {
+    let mut lines: Vec<String> = state
+        .keymap
+        .bindings(&state.mode)
+        .map(|binding| {
+            format!(
+                "{:>5}  {}",
+                binding.keys_label(),
+                binding.action.description()
+            )
+        })
+        .collect();
+
+    if state.mode == Mode::Command {
+        lines.push("________________".to_string());
+        lines.extend(Command::ALL.iter().map(|command| command.usage()));
+    }
 
-    Paragraph::new(shortcuts)
+    lines
+}
+
+pub fn help(state: &State) -> Paragraph<'static> {
+    Paragraph::new(shortcuts(state).join("\n"))
         .block(
             Block::default()
                 .title(format!("Help ({})", state.mode))
                 .borders(Borders::all()),
         )
-        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .style(state.theme.help())
 }
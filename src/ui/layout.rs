@@ -0,0 +1,147 @@
+use tui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    widgets::{Paragraph, Wrap},
+};
+
+/// Smallest terminal the retro can be held in, anything smaller gets a
+/// message asking for more room
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 12;
+
+/// Width of the participants sidebar
+const SIDEBAR_WIDTH: u16 = 24;
+
+/// The note editor is at least a line of text and its borders tall
+const EDITOR_MIN_HEIGHT: u16 = 3;
+
+/// Height of the command box, including its borders
+const COMMAND_HEIGHT: u16 = 3;
+
+#[derive(Clone, Copy, Debug)]
+/// Where everything goes on the screen
+pub struct Areas {
+    pub notes: Rect,
+    pub dashboard: Option<Rect>,
+    pub participants: Option<Rect>,
+
+    /// Mode, room, progress and participant count, on the bottom line
+    pub mode: Rect,
+    pub room: Rect,
+    pub progress: Rect,
+    pub participant_count: Rect,
+
+    /// The note editor in insert mode
+    pub editor: Rect,
+
+    /// The command box in command mode, just above the status bar
+    pub command: Rect,
+
+    /// Everything above the status bar, popups and toasts go in here
+    pub main: Rect,
+}
+
+impl Areas {
+    pub fn new(size: Rect, show_dashboard: bool, show_participants: bool) -> Self {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(size);
+        let (main, status) = (rows[0], rows[1]);
+
+        let sidebar = if show_participants {
+            SIDEBAR_WIDTH.min(main.width / 3)
+        } else {
+            0
+        };
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(sidebar)])
+            .split(main);
+
+        // Split separately, the solver is unreliable when ratios share a
+        // layout with fixed lengths
+        let (notes, dashboard) = if show_dashboard {
+            let halves = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Ratio(3, 5), Constraint::Ratio(2, 5)])
+                .split(columns[0]);
+            (halves[0], Some(halves[1]))
+        } else {
+            (columns[0], None)
+        };
+
+        let status = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(6),
+                Constraint::Length(31),
+                Constraint::Min(0),
+                Constraint::Length(17),
+            ])
+            .split(status);
+
+        // The bottom quarter, leaving a line free above the status bar
+        let editor_height = (size.height / 4).saturating_sub(2).max(EDITOR_MIN_HEIGHT);
+        let editor = Layout::default()
+            .direction(Direction::Vertical)
+            .horizontal_margin(1)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(editor_height),
+                Constraint::Length(1),
+            ])
+            .split(main)[1];
+
+        let command = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(COMMAND_HEIGHT)])
+            .split(main)[1];
+
+        Areas {
+            notes,
+            dashboard,
+            participants: show_participants.then_some(columns[1]),
+            mode: status[0],
+            room: status[1],
+            progress: status[2],
+            participant_count: status[3],
+            editor,
+            command,
+            main,
+        }
+    }
+
+    /// Where the help box goes: a third of the width on the right, above the
+    /// command box, as tall as its contents if there's room
+    pub fn help(&self, lines: usize) -> Rect {
+        let height = (lines as u16).saturating_add(2);
+        let above_command = self.main.height.saturating_sub(COMMAND_HEIGHT);
+        let height = height.min(above_command);
+        let width = self.main.width / 3;
+
+        Rect::new(
+            self.main.right().saturating_sub(width + 4),
+            self.main.y + above_command - height,
+            width,
+            height,
+        )
+    }
+}
+
+/// If the position is inside the area
+pub fn contains(area: Rect, column: u16, row: u16) -> bool {
+    (area.left()..area.right()).contains(&column) && (area.top()..area.bottom()).contains(&row)
+}
+
+/// Shown instead of the retro when the terminal is smaller than
+/// [`MIN_WIDTH`] by [`MIN_HEIGHT`]
+pub fn too_small(size: Rect) -> Paragraph<'static> {
+    let top_padding = "\n".repeat((size.height / 2).saturating_sub(1) as usize);
+
+    Paragraph::new(format!(
+        "{top_padding}Terminal too small\n{}×{}, needs at least {MIN_WIDTH}×{MIN_HEIGHT}",
+        size.width, size.height
+    ))
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+}
@@ -1,6 +1,14 @@
 pub mod command_textbox;
+pub mod dashboard;
 pub mod help;
+pub mod layout;
 pub mod new_note;
+pub mod note_detail;
 pub mod notes_list;
+pub mod notifications;
+pub mod participants;
+pub mod reaction_picker;
 pub mod room_info;
 pub mod status_bar;
+pub mod text;
+pub mod trends;
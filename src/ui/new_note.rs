@@ -1,18 +1,57 @@
 use tui::{
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
     widgets::{Block, Borders},
 };
 use tui_textarea::TextArea;
 
-pub fn new_note() -> TextArea<'static> {
+use crate::app::{
+    note::{sentiment_label, Note, MAX_LENGTH},
+    state::State,
+};
+
+use crate::app::theme::Theme;
+
+pub fn new_note(theme: &Theme) -> TextArea<'static> {
     let mut textarea = TextArea::default();
 
     textarea.set_block(
         Block::default()
             .borders(Borders::ALL)
             .title("Note")
-            .style(Style::default().bg(Color::DarkGray).fg(Color::White)),
+            .style(Style::default().bg(theme.editor_bg).fg(theme.editor_fg)),
     );
 
     textarea
 }
+
+/// The frame around the editor. While writing a note the title previews the
+/// sentiment the note will get and counts down to the length limit.
+pub fn note_block(state: &State, textarea: &TextArea<'_>) -> Block<'static> {
+    let theme = &state.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().bg(theme.editor_bg).fg(theme.editor_fg));
+
+    if state.open_note.is_some() && state.editing.is_none() {
+        return block.title("Comment");
+    }
+
+    let text = textarea.lines().join("\n");
+    let (sentiment, inferred) = Note::classify(&text, state.classifier.as_ref());
+    let preview = sentiment_label(sentiment, inferred);
+    let length = text.chars().count();
+    let limit_style = match (length > MAX_LENGTH, theme.warning) {
+        (false, _) => Style::default(),
+        (true, Color::Reset) => Style::default().add_modifier(Modifier::REVERSED),
+        (true, color) => Style::default().fg(color).add_modifier(Modifier::BOLD),
+    };
+
+    block.title(Spans::from(vec![
+        Span::raw(match state.editing {
+            Some(_) => format!("Edit {preview} "),
+            None => format!("Note {preview} "),
+        }),
+        Span::styled(format!("{length}/{MAX_LENGTH}"), limit_style),
+    ]))
+}
@@ -0,0 +1,161 @@
+use chrono::{Local, TimeZone};
+use tui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::{keymap::Action, mode::Mode, state::State};
+
+pub fn note_detail(state: &State) -> Paragraph<'static> {
+    let mut lines: Vec<Spans<'static>> = vec![];
+    let mut title = "Note".to_string();
+
+    if let Some(note) = state.get_open_note() {
+        let voted = if state.voted_for(&note.id) {
+            "  (voted)"
+        } else {
+            ""
+        };
+        lines.push(Spans::from(vec![
+            Span::styled(
+                note.author.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "  {}  [+{}]{voted}  {}",
+                note.sentiment_label(),
+                note.votes,
+                note.reaction_summary()
+            )),
+        ]));
+
+        let created = note.created_at.map(format_time);
+        let updated = note
+            .updated_at
+            .filter(|updated| Some(*updated) != note.created_at);
+        match (created, updated.map(format_time)) {
+            (Some(created), Some(updated)) => {
+                lines.push(Spans::from(format!("Created {created}, changed {updated}")))
+            }
+            (Some(created), None) => lines.push(Spans::from(format!("Created {created}"))),
+            _ => {}
+        }
+
+        if let Some(group) = &note.group {
+            let others = state
+                .notes
+                .iter()
+                .filter(|other| other.id != note.id && other.group.as_ref() == Some(group))
+                .count();
+            lines.push(Spans::from(format!(
+                "Group: {group}, with {others} other note{}",
+                if others == 1 { "" } else { "s" }
+            )));
+        }
+
+        if note.action {
+            let status = if note.done { "done" } else { "open" };
+            lines.push(Spans::from(format!("Action item, {status}")));
+        }
+
+        let voters = if state.anonymous {
+            "hidden, the room is anonymous".to_string()
+        } else if note.voters.is_empty() {
+            "none yet".to_string()
+        } else {
+            note.voters
+                .iter()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        lines.push(Spans::from(format!("Voters: {voters}")));
+
+        lines.push(Spans::default());
+        lines.extend(note.text.lines().map(|line| Spans::from(line.to_string())));
+        lines.push(Spans::default());
+
+        // Only the author gets to change the note
+        let mut actions = vec![Action::Comment, Action::ToggleVote, Action::React];
+        if note.author == state.display_name {
+            actions.extend([Action::EditNote, Action::DeleteNote]);
+        }
+        let hints: Vec<String> = actions
+            .iter()
+            .filter_map(|action| {
+                state
+                    .keymap
+                    .bindings(&Mode::Detail)
+                    .find(|binding| binding.action == *action)
+                    .map(|binding| format!("{} {}", binding.keys_label(), action.description()))
+            })
+            .collect();
+        title = format!("Note ({})", hints.join(", "));
+    }
+
+    lines.push(Spans::from(Span::styled(
+        format!("Comments ({})", state.comments.len()),
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    lines.extend(
+        state
+            .comments
+            .iter()
+            .map(|comment| Spans::from(format!("  {comment}"))),
+    );
+
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::all()).title(title))
+        .wrap(Wrap { trim: false })
+}
+
+/// Where the detail popup goes on the screen
+pub fn detail_area(screen: Rect) -> Rect {
+    centered(screen, 60, 60)
+}
+
+/// Where the `[+n]` badge of the open note is drawn, in the first line of
+/// the popup
+pub fn vote_badge(area: Rect, state: &State) -> Option<Rect> {
+    let note = state.get_open_note()?;
+    let inner = Block::default().borders(Borders::all()).inner(area);
+
+    let before = format!("{}  {}  ", note.author, note.sentiment_label()).width() as u16;
+    let badge = format!("[+{}]", note.votes).width() as u16;
+
+    Some(Rect::new(inner.x + before, inner.y, badge, 1).intersection(inner))
+}
+
+/// A unix timestamp in the local timezone, such as `2023-02-03 14:05`
+fn format_time(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// A rect of the given percentage of the area, centered in it
+pub fn centered(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    centered_size(
+        area,
+        area.width * percent_x / 100,
+        area.height * percent_y / 100,
+    )
+}
+
+/// A rect of the given size centered in the area, shrunk to fit if needed
+pub fn centered_size(area: Rect, width: u16, height: u16) -> Rect {
+    let (width, height) = (width.min(area.width), height.min(area.height));
+
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
@@ -1,43 +1,243 @@
+use chrono::Utc;
 use tui::{
-    style::{Color, Style},
-    widgets::{Block, Borders, List, ListItem},
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Spans, Text},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
 };
+use unicode_width::UnicodeWidthStr;
 
-use crate::app::{mode::Mode, note::Note, state::State};
+use crate::app::{
+    mode::Mode,
+    note::Note,
+    state::State,
+    text::{truncate, wrap},
+};
+
+use super::{layout::contains, text::relative_time};
+
+/// Notes get at least this many columns of text, however narrow the list
+const MIN_TEXT_WIDTH: usize = 10;
+
+#[derive(Debug, Default)]
+/// The part of the notes list that's on screen. It follows the cursor when
+/// the cursor moves, and can be scrolled away from it with the mouse wheel.
+pub struct NotesView {
+    /// Index of the first note shown
+    offset: usize,
+
+    /// The cursor as of the last draw, None when it wasn't shown
+    cursor: Option<usize>,
+
+    /// Where the notes on screen were drawn, for finding what was clicked
+    shown: Vec<ShownNote>,
+}
+
+#[derive(Debug)]
+struct ShownNote {
+    index: usize,
+    area: Rect,
+
+    /// The `[+n]` badge, if the note has votes
+    votes: Option<Rect>,
+}
+
+impl NotesView {
+    /// Scroll up (negative) or down (positive) by a number of notes, the end
+    /// of the list is found on the next draw
+    pub fn scroll(&mut self, delta: isize) {
+        self.offset = self.offset.saturating_add_signed(delta);
+    }
 
-pub fn notes_list(state: &State) -> List<'static> {
-    let items: Vec<ListItem<'static>> = state
+    /// Index of the note drawn at the given position
+    pub fn note_at(&self, column: u16, row: u16) -> Option<usize> {
+        self.shown
+            .iter()
+            .find(|shown| contains(shown.area, column, row))
+            .map(|shown| shown.index)
+    }
+
+    /// Index of the note whose vote badge is at the given position
+    pub fn votes_at(&self, column: u16, row: u16) -> Option<usize> {
+        self.shown
+            .iter()
+            .find(|shown| {
+                shown
+                    .votes
+                    .is_some_and(|votes| contains(votes, column, row))
+            })
+            .map(|shown| shown.index)
+    }
+}
+
+/// Draw the notes that fit in the area, starting from the view's offset
+pub fn notes_list<B: Backend>(
+    ui: &mut Frame<'_, B>,
+    area: Rect,
+    state: &State,
+    view: &mut NotesView,
+) {
+    let block = Block::default()
+        .borders(Borders::all())
+        .title(format!("Notes ({})", state.sort));
+    let inner = block.inner(area);
+    let (width, height) = (inner.width as usize, inner.height as usize);
+
+    let texts: Vec<Text<'static>> = state
         .notes
         .iter()
         .enumerate()
-        .map(|(index, note)| {
-            ListItem::new(display_note(note, &state.mode, &index)).style(get_style(&index, state))
-        })
+        .map(|(index, note)| display_note(note, state, &index, width))
         .collect();
+    let heights: Vec<usize> = texts.iter().map(Text::height).collect();
+
+    // Bring the cursor into view when it moved since the last draw
+    let show_cursor = matches!(state.mode, Mode::Normal | Mode::Detail);
+    let cursor = Some(state.cursor).filter(|cursor| show_cursor && *cursor < texts.len());
+    if let Some(cursor) = cursor.filter(|_| cursor != view.cursor) {
+        view.offset = view.offset.min(cursor);
+        while view.offset < cursor && heights[view.offset..=cursor].iter().sum::<usize>() > height {
+            view.offset += 1;
+        }
+    }
+    view.cursor = cursor;
+
+    // Stop scrolling once the last note is at the bottom
+    let mut last_page = texts.len();
+    let mut last_page_height = 0;
+    while last_page > 0 && last_page_height + heights[last_page - 1] <= height {
+        last_page -= 1;
+        last_page_height += heights[last_page];
+    }
+    view.offset = view
+        .offset
+        .min(last_page)
+        .min(texts.len().saturating_sub(1));
 
-    List::new(items).block(Block::default().borders(Borders::all()).title("Notes"))
+    view.shown.clear();
+    let mut items = vec![];
+    let mut y = inner.y;
+    for (index, text) in texts.into_iter().enumerate().skip(view.offset) {
+        let text_height = text.height() as u16;
+        // The first note is shown even when it's taller than the list
+        if !items.is_empty() && y + text_height > inner.bottom() {
+            break;
+        }
+
+        let note = &state.notes[index];
+        let note_area = Rect::new(inner.x, y, inner.width, text_height).intersection(inner);
+        let votes = (note.votes > 0).then(|| {
+            // The footer ends the last line, and starts with the votes
+            let last_line = text.lines.last().map_or(0, Spans::width) as u16;
+            let start = last_line.saturating_sub(footer(note, state).width() as u16);
+            let badge = format!("[+{}]", note.votes).width() as u16;
+            Rect::new(inner.x + start, y + text_height - 1, badge, 1).intersection(inner)
+        });
+        view.shown.push(ShownNote {
+            index,
+            area: note_area,
+            votes,
+        });
+
+        items.push(ListItem::new(text).style(get_style(note, state)));
+        y += text_height;
+    }
+
+    let mut list_state = ListState::default();
+    list_state.select(
+        cursor
+            .filter(|cursor| view.shown.iter().any(|shown| shown.index == *cursor))
+            .map(|cursor| cursor - view.offset),
+    );
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(state.theme.cursor());
+    ui.render_stateful_widget(list, area, &mut list_state);
 }
 
-pub fn display_note(note: &Note, mode: &Mode, index: &usize) -> String {
-    match mode {
-        Mode::Command => format!("{index} {note}",),
-        _ => note.to_string(),
+/// A note as shown in the list, wrapped to the width of the list with the
+/// lines after the first one lined up under the start of the text. In
+/// compact mode notes are cut off at the end of the first line instead.
+pub fn display_note(note: &Note, state: &State, index: &usize, width: usize) -> Text<'static> {
+    let header = match state.mode {
+        Mode::Command => format!("{index} {}", note.header()),
+        _ => note.header(),
+    };
+    let footer = footer(note, state);
+    let footer = footer.as_str();
+
+    // Narrow terminals still get a few columns of text
+    let text_width = width.saturating_sub(header.width()).max(MIN_TEXT_WIDTH);
+
+    if state.compact {
+        let text = note
+            .text
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let text_width = text_width
+            .saturating_sub(footer.width() + 1)
+            .max(MIN_TEXT_WIDTH);
+
+        return Text::from(format!("{header}{} {footer}", truncate(&text, text_width)));
+    }
+
+    let indent = " ".repeat(header.width());
+    let mut lines = wrap(&note.text, text_width);
+
+    // The footer goes after the text, or on a line of its own if it
+    // doesn't fit
+    if !footer.is_empty() {
+        let last = lines.last_mut().expect("wrap yields at least one line");
+        if last.width() + 1 + footer.width() <= text_width {
+            last.push(' ');
+            last.push_str(footer);
+        } else {
+            lines.push(footer.to_string());
+        }
     }
+
+    let lines: Vec<Spans<'static>> = lines
+        .into_iter()
+        .enumerate()
+        .map(|(number, line)| match number {
+            0 => Spans::from(format!("{header}{line}")),
+            _ => Spans::from(format!("{indent}{line}")),
+        })
+        .collect();
+
+    Text::from(lines)
 }
 
-fn get_style(index: &usize, state: &State) -> Style {
-    let is_included = state.selected_rows.contains(index);
+/// Votes, reactions and comments, followed by the age of the note when
+/// relative times are on
+fn footer(note: &Note, state: &State) -> String {
+    let footer = note.footer();
 
-    let bg = match state.mode {
-        Mode::Command => {
-            if is_included {
-                Color::LightGreen
-            } else {
-                Color::Reset
-            }
+    match note.created_at.filter(|_| state.relative_time) {
+        Some(created_at) => {
+            let ago = relative_time(Utc::now().timestamp() - created_at);
+            format!("{footer} {ago}").trim_start().to_string()
         }
-        _ => Color::Reset,
+        None => footer,
+    }
+}
+
+fn get_style(note: &Note, state: &State) -> Style {
+    let is_included = state.is_selected(&note.id);
+
+    let style = match (&state.mode, is_included) {
+        (Mode::Command, true) => state.theme.selected(),
+        _ => Style::default(),
     };
 
-    Style::default().bg(bg)
+    // Dimmed until the backend confirms the change
+    if note.pending {
+        style.add_modifier(Modifier::DIM)
+    } else {
+        style
+    }
 }
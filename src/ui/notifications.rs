@@ -0,0 +1,68 @@
+use tui::{
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use crate::app::{
+    notification::{Level, Notification},
+    state::State,
+};
+
+/// Height of a single toast, including its borders
+pub const TOAST_HEIGHT: u16 = 3;
+
+/// A transient notification, stacked in the bottom right corner
+pub fn toast(notification: &Notification, state: &State) -> Paragraph<'static> {
+    Paragraph::new(notification.message.clone())
+        .block(
+            Block::default()
+                .borders(Borders::all())
+                .title(notification.level.to_string())
+                .border_style(level_style(notification.level, state)),
+        )
+        .style(state.theme.help())
+}
+
+/// Every notification that's still in the log, newest first
+pub fn messages(state: &State) -> Paragraph<'static> {
+    let lines: Vec<Spans<'static>> = state
+        .notifications
+        .iter()
+        .rev()
+        .map(|notification| {
+            Spans::from(vec![
+                Span::raw(format!("{} ", notification.time.format("%H:%M:%S"))),
+                Span::styled(
+                    format!("{:<8}", notification.level),
+                    level_style(notification.level, state),
+                ),
+                Span::raw(notification.message.clone()),
+            ])
+        })
+        .collect();
+
+    let lines = if lines.is_empty() {
+        vec![Spans::from("No messages yet")]
+    } else {
+        lines
+    };
+
+    Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::all())
+                .title(format!("Messages ({})", state.notifications.len())),
+        )
+        .wrap(Wrap { trim: false })
+}
+
+fn level_style(level: Level, state: &State) -> Style {
+    match level {
+        Level::Info => Style::default(),
+        Level::Warning => Style::default().fg(state.theme.warning),
+        Level::Error => Style::default()
+            .fg(state.theme.warning)
+            .add_modifier(Modifier::BOLD),
+    }
+}
@@ -0,0 +1,33 @@
+use chrono::Utc;
+use tui::{
+    style::Style,
+    widgets::{Block, Borders, List, ListItem},
+};
+
+use crate::app::{participant::Presence, state::State};
+
+pub fn participants(state: &State) -> List<'static> {
+    let now = Utc::now().timestamp();
+
+    let items: Vec<ListItem<'static>> = state
+        .participants
+        .iter()
+        .map(|participant| {
+            let presence = participant.effective_presence(now);
+            let color = match presence {
+                Presence::Online => state.theme.online,
+                Presence::Idle => state.theme.idle,
+                Presence::Typing => state.theme.typing,
+            };
+
+            ListItem::new(format!("{presence} {}", participant.display_name))
+                .style(Style::default().fg(color))
+        })
+        .collect();
+
+    List::new(items).block(
+        Block::default()
+            .borders(Borders::all())
+            .title(format!("Online ({})", state.participants.len())),
+    )
+}
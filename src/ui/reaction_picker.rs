@@ -0,0 +1,16 @@
+use tui::widgets::{Block, Borders, Paragraph};
+
+use crate::app::{reaction::Reaction, state::State};
+
+pub fn reaction_picker(state: &State) -> Paragraph<'static> {
+    let reactions = Reaction::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, reaction)| format!("{} {reaction}", index + 1))
+        .collect::<Vec<String>>()
+        .join("  ");
+
+    Paragraph::new(reactions)
+        .block(Block::default().title("React").borders(Borders::all()))
+        .style(state.theme.help())
+}
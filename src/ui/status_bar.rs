@@ -1,5 +1,5 @@
 use tui::{
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::Block,
 };
 
@@ -8,8 +8,8 @@ use crate::app::state::State;
 pub fn status_bar(state: &State) -> Block<'static> {
     Block::default().title(state.mode.to_string()).style(
         Style::default()
-            .fg(Color::Reset)
-            .bg(state.mode.get_color())
+            .fg(state.theme.status_fg)
+            .bg(state.theme.mode_color(&state.mode))
             .add_modifier(Modifier::BOLD),
     )
 }
@@ -0,0 +1,10 @@
+/// How long ago something happened, such as `3m ago`, given its age in
+/// seconds
+pub fn relative_time(seconds: i64) -> String {
+    match seconds {
+        ..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86_399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86_400),
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{TimeZone, Utc};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    widgets::{BarChart, Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{state::State, stats::RoomStats};
+
+/// Reads one number out of the stats of a retro
+type Metric = fn(&RoomStats) -> u64;
+
+/// Draw how the retros in the room's series went over time, one bar per
+/// retro, oldest on the left
+pub fn trends<B: Backend>(ui: &mut Frame<'_, B>, area: Rect, state: &State) {
+    let title = match &state.series {
+        Some(series) => format!("Trends ({series})"),
+        None => "Trends".to_string(),
+    };
+    let block = Block::default().borders(Borders::all()).title(title);
+    let inner = block.inner(area);
+    ui.render_widget(block, area);
+
+    if state.series.is_none() {
+        ui.render_widget(
+            Paragraph::new("This room isn't part of a series, see `retro create --series`"),
+            inner,
+        );
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(25); 4])
+        .split(inner);
+
+    let dates: Vec<String> = state
+        .trends
+        .iter()
+        .map(|room| {
+            Utc.timestamp_opt(room.created_at, 0)
+                .single()
+                .map(|date| date.format("%m-%d").to_string())
+                .unwrap_or_default()
+        })
+        .collect();
+    let charts: [(&str, Metric); 4] = [
+        ("Happy %", |room| percent(room.sentiment_ratio())),
+        ("Notes", |room| room.notes as u64),
+        ("People", |room| room.participants as u64),
+        ("Actions done %", |room| percent(room.completion_rate())),
+    ];
+
+    for ((title, value), area) in charts.into_iter().zip(rows.iter()) {
+        let data: Vec<(&str, u64)> = dates
+            .iter()
+            .zip(state.trends.iter())
+            .map(|(date, room)| (date.as_str(), value(room)))
+            .collect();
+
+        ui.render_widget(
+            BarChart::default()
+                .block(Block::default().borders(Borders::TOP).title(title))
+                .data(&data)
+                .bar_width(5)
+                .bar_gap(1)
+                .bar_style(Style::default().fg(state.theme.chart)),
+            *area,
+        );
+    }
+}
+
+fn percent(ratio: Option<f64>) -> u64 {
+    (ratio.unwrap_or(0.0) * 100.0).round() as u64
+}